@@ -0,0 +1,61 @@
+//! Measures the throughput of [Subleq::step] on a multiplication routine.
+//!
+//! Run with `cargo bench`; criterion compares every run with the previous one.
+
+use std::hint::black_box;
+
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use qelbus::{
+    Memory, Subleq,
+    memory::{LinearMemory, SparseMemory},
+    subasm::compile,
+};
+
+/// The amount of instructions executed per iteration.
+const STEPS: u64 = 100_000;
+
+/// Multiply X by Y into P by adding X to P Y times.
+/// Y is large enough that the program doesn't halt within [STEPS] instructions.
+const MULTIPLY: &str = "
+    loop: ADD X P
+          ONE Y done
+          JMP loop
+    done: HALT
+
+    X: 7
+    Y: 1000000
+    P: 0
+    ONE: 1
+";
+
+/// Execute [STEPS] instructions of a fresh copy of `subleq`.
+fn run<T, M>(subleq: &Subleq<T, M>)
+where
+    T: qelbus::Word,
+    M: Memory<T> + Clone,
+{
+    let mut subleq = subleq.clone();
+    for _ in 0..STEPS {
+        black_box(subleq.step().unwrap());
+    }
+}
+
+/// Benchmark the multiplication routine on different memories.
+fn step(c: &mut Criterion) {
+    let program = compile::<i32>(MULTIPLY).unwrap();
+    let mut group = c.benchmark_group("step");
+    group.throughput(Throughput::Elements(STEPS));
+
+    let linear = Subleq::<i32, LinearMemory<i32, 64>>::with_program(&program);
+    group.bench_function("linear_i32", |b| b.iter(|| run(&linear)));
+
+    let mut memory = SparseMemory::new();
+    memory.set_range(&0, &program).unwrap();
+    let sparse = Subleq::new(memory);
+    group.bench_function("sparse_i32", |b| b.iter(|| run(&sparse)));
+
+    group.finish();
+}
+
+criterion_group!(benches, step);
+criterion_main!(benches);
@@ -0,0 +1,369 @@
+//! Assemble subleq programs from a simple text format.
+//!
+//! A program consists of cell values separated by whitespace or commas.
+//! Every value is one of:
+//! - an integer, like `7` or `-1`,
+//! - a label reference, like `loop`, which is replaced by the address of the label,
+//! - `?`, which is replaced by the address of the next cell.
+//!
+//! A label is defined by writing its name followed by a colon, like `loop:`,
+//! and refers to the address of the cell that follows it.
+//! Everything after a `;` up to the end of the line is a comment.
+//!
+//! The directives `.text` and `.data` switch between the code and the data section,
+//! a program starts in the code section.
+//! The code section is placed first, followed by the data section,
+//! so code and data can be written in any order, see [Layout].
+//!
+//! ```text
+//! ; subtract one from X until it is no longer positive
+//! .data
+//! ONE: 1
+//! X: 3
+//! Z: 0
+//!
+//! .text
+//! loop: ONE X end
+//!       Z Z loop
+//! end:  Z Z -1
+//! ```
+
+use core::ops::Range;
+use std::collections::HashMap;
+
+use num::{FromPrimitive, Num};
+
+use crate::token_column;
+
+/// An error while assembling a program, at the position of the token which caused it.
+#[derive(thiserror::Error, Clone, Eq, PartialEq, Hash, Debug)]
+#[error("line {line}, column {column}: {kind}")]
+pub struct AssembleError {
+    /// The line of the token, starting at 1.
+    pub line: usize,
+    /// The byte offset of the token in its line, starting at 1.
+    pub column: usize,
+    /// What is wrong with the token.
+    pub kind: AssembleErrorKind,
+}
+
+/// The kind of an [AssembleError].
+#[derive(thiserror::Error, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum AssembleErrorKind {
+    /// A value looked like an integer, but couldn't be parsed into one.
+    #[error("invalid integer `{0}`")]
+    InvalidInteger(String),
+    /// A value is an integer, but doesn't fit in the word type.
+    #[error("integer `{0}` does not fit in the word type")]
+    Overflow(String),
+    /// A value is neither an integer, a label reference nor `?`.
+    #[error("invalid token `{0}`")]
+    InvalidToken(String),
+    /// A label is referenced, but never defined.
+    #[error("unknown label `{0}`")]
+    UnknownLabel(String),
+    /// A label is defined more than once.
+    #[error("label `{0}` is defined more than once")]
+    DuplicateLabel(String),
+    /// A directive is neither `.text` nor `.data`.
+    #[error("unknown directive `{0}`")]
+    UnknownDirective(String),
+    /// An address doesn't fit in the word type.
+    #[error("address {0} does not fit in the word type")]
+    AddressOverflow(usize),
+}
+
+impl AssembleErrorKind {
+    /// Attach the position of the token to the error.
+    fn at(self, line: usize, column: usize) -> AssembleError {
+        AssembleError {
+            line,
+            column,
+            kind: self,
+        }
+    }
+}
+
+/// Where the sections of an assembled program lie, see [assemble_with_layout].
+///
+/// The code section starts at address 0 and the data section follows it, so together they cover the program.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Layout<T> {
+    /// The addresses of the cells in the `.text` section.
+    pub code: Range<T>,
+    /// The addresses of the cells in the `.data` section.
+    pub data: Range<T>,
+}
+
+/// A section of a program, which is selected by a directive.
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
+enum Section {
+    /// The `.text` section, which is placed first.
+    Code,
+    /// The `.data` section, which is placed after the code section.
+    Data,
+}
+
+/// A single value in the source of a program.
+enum Value<'a> {
+    /// An integer literal.
+    Integer(&'a str),
+    /// A reference to a label.
+    Label(&'a str),
+    /// The address of the next cell.
+    Next,
+}
+
+/// Assemble a program into the values of its cells, see the [module documentation](self)
+/// for the format.
+///
+/// ```
+/// # use qelbus::{Subleq, asm::assemble, memory::LinearMemory};
+/// let program = assemble::<i32>("
+///     loop: ONE X end
+///           Z Z loop
+///     end:  Z Z -1
+///
+///     ONE: 1
+///     X: 3
+///     Z: 0
+/// ").unwrap();
+/// assert_eq!(program, [9, 10, 6, 11, 11, 0, 11, 11, -1, 1, 3, 0]);
+///
+/// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&program);
+/// subleq.run().unwrap();
+/// ```
+///
+/// ```
+/// # use qelbus::asm::{AssembleError, AssembleErrorKind, assemble};
+/// assert_eq!(
+///     assemble::<i32>("Z: 0\nZ Z missing"),
+///     Err(AssembleError {
+///         line: 2,
+///         column: 5,
+///         kind: AssembleErrorKind::UnknownLabel("missing".to_owned()),
+///     }),
+/// );
+/// assert_eq!(
+///     assemble::<i8>("  1000"),
+///     Err(AssembleError {
+///         line: 1,
+///         column: 3,
+///         kind: AssembleErrorKind::Overflow("1000".to_owned()),
+///     }),
+/// );
+/// assert_eq!(
+///     assemble::<i32>("1 1x"),
+///     Err(AssembleError {
+///         line: 1,
+///         column: 3,
+///         kind: AssembleErrorKind::InvalidInteger("1x".to_owned()),
+///     }),
+/// );
+/// assert_eq!(
+///     assemble::<i32>("1 .bss 2"),
+///     Err(AssembleError {
+///         line: 1,
+///         column: 3,
+///         kind: AssembleErrorKind::UnknownDirective(".bss".to_owned()),
+///     }),
+/// );
+/// ```
+///
+/// # Errors
+/// Returns an [AssembleError] with the position of the first token
+/// which makes the source an invalid program.
+pub fn assemble<T>(source: &str) -> Result<Vec<T>, AssembleError>
+where
+    T: Num + FromPrimitive,
+{
+    assemble_sections(source).map(|sections| sections.program)
+}
+
+/// Assemble a program like [assemble] and also return where its code and data sections lie.
+///
+/// The layout tells tools which part of the program is code,
+/// for example to check it with [analyze_writes](crate::disasm::analyze_writes)
+/// or to watch the data with [Subleq::add_watchpoint](crate::Subleq::add_watchpoint).
+///
+/// ```
+/// # use qelbus::{asm::{Layout, assemble_with_layout}, disasm::analyze_writes, memory::LinearMemory};
+/// let (program, layout) = assemble_with_layout::<i32>("
+///     .data
+///     ONE: 1
+///     X: 3
+///     Z: 0
+///
+///     .text
+///     loop: ONE X end
+///           Z Z loop
+///     end:  Z Z -1
+/// ").unwrap();
+/// assert_eq!(program, [9, 10, 6, 11, 11, 0, 11, 11, -1, 1, 3, 0]);
+/// assert_eq!(layout, Layout { code: 0..9, data: 9..12 });
+///
+/// let memory = LinearMemory::<i32, 16>::from_slice(&program).unwrap();
+/// assert!(!analyze_writes(&memory, layout.code).unwrap().is_self_modifying());
+/// ```
+///
+/// # Errors
+/// Returns an [AssembleError] with the position of the first token
+/// which makes the source an invalid program,
+/// or with [AssembleErrorKind::AddressOverflow] at the last token of a section
+/// if the end of the section doesn't fit in the word type.
+pub fn assemble_with_layout<T>(source: &str) -> Result<(Vec<T>, Layout<T>), AssembleError>
+where
+    T: Num + FromPrimitive,
+{
+    let sections = assemble_sections(source)?;
+    let end = |len: usize| {
+        to_address(len).map_err(|kind| {
+            let (line, column) = sections.positions[len - 1];
+            kind.at(line, column)
+        })
+    };
+    let layout = Layout {
+        code: T::zero()..end(sections.code_len)?,
+        data: end(sections.code_len)?..end(sections.program.len())?,
+    };
+    Ok((sections.program, layout))
+}
+
+/// An assembled program, see [assemble_sections].
+struct Sections<T> {
+    /// The values of the cells.
+    program: Vec<T>,
+    /// The amount of cells in the code section, which comes first.
+    code_len: usize,
+    /// The line and column of the token of every cell.
+    positions: Vec<(usize, usize)>,
+}
+
+/// Assemble a program and return it together with the length of its code section
+/// and the positions of its cells.
+fn assemble_sections<T>(source: &str) -> Result<Sections<T>, AssembleError>
+where
+    T: Num + FromPrimitive,
+{
+    let mut labels = HashMap::new();
+    let mut code = Vec::new();
+    let mut data = Vec::new();
+    let mut section = Section::Code;
+
+    for (line_index, line) in source.lines().enumerate() {
+        let line_number = line_index + 1;
+        let text = line.split(';').next().unwrap_or_default();
+
+        for word in text.split(|c: char| c.is_whitespace() || c == ',') {
+            let values = match section {
+                Section::Code => &mut code,
+                Section::Data => &mut data,
+            };
+            let mut rest = word;
+            while let Some((label, after)) = rest.split_once(':') {
+                let column = token_column(line, label);
+                if !is_identifier(label) {
+                    return Err(
+                        AssembleErrorKind::InvalidToken(label.to_owned()).at(line_number, column)
+                    );
+                }
+                if labels.insert(label, (section, values.len())).is_some() {
+                    return Err(
+                        AssembleErrorKind::DuplicateLabel(label.to_owned()).at(line_number, column)
+                    );
+                }
+                rest = after;
+            }
+
+            if rest.is_empty() {
+                continue;
+            }
+            let column = token_column(line, rest);
+            if let Some(directive) = rest.strip_prefix('.') {
+                section = match directive {
+                    "text" => Section::Code,
+                    "data" => Section::Data,
+                    _ => {
+                        return Err(AssembleErrorKind::UnknownDirective(rest.to_owned())
+                            .at(line_number, column));
+                    }
+                };
+                continue;
+            }
+            let value = parse_value(rest).map_err(|error| error.at(line_number, column))?;
+            values.push((value, line_number, column));
+        }
+    }
+
+    let code_len = code.len();
+    let cells: Vec<_> = code.into_iter().chain(data).collect();
+    let positions = cells
+        .iter()
+        .map(|&(_, line, column)| (line, column))
+        .collect();
+    let program = cells
+        .into_iter()
+        .enumerate()
+        .map(|(address, (value, line, column))| {
+            match value {
+                Value::Integer(integer) => parse_integer(integer),
+                Value::Label(label) => labels
+                    .get(label)
+                    .ok_or_else(|| AssembleErrorKind::UnknownLabel(label.to_owned()))
+                    .and_then(|&(section, index)| match section {
+                        Section::Code => to_address(index),
+                        Section::Data => to_address(code_len + index),
+                    }),
+                Value::Next => to_address(address + 1),
+            }
+            .map_err(|error| error.at(line, column))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Sections {
+        program,
+        code_len,
+        positions,
+    })
+}
+
+/// Parse an integer literal, distinguishing integers which don't fit in `T` from invalid ones.
+fn parse_integer<T>(integer: &str) -> Result<T, AssembleErrorKind>
+where
+    T: Num,
+{
+    T::from_str_radix(integer, 10).map_err(|_| {
+        let digits = integer.strip_prefix(['-', '+']).unwrap_or(integer);
+        if !digits.is_empty() && digits.bytes().all(|byte| byte.is_ascii_digit()) {
+            AssembleErrorKind::Overflow(integer.to_owned())
+        } else {
+            AssembleErrorKind::InvalidInteger(integer.to_owned())
+        }
+    })
+}
+
+/// Parse a single non-empty value.
+fn parse_value(token: &str) -> Result<Value<'_>, AssembleErrorKind> {
+    if token == "?" {
+        Ok(Value::Next)
+    } else if token.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '+') {
+        Ok(Value::Integer(token))
+    } else if is_identifier(token) {
+        Ok(Value::Label(token))
+    } else {
+        Err(AssembleErrorKind::InvalidToken(token.to_owned()))
+    }
+}
+
+/// Check whether `name` is a valid label name.
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Convert a cell index into an address.
+fn to_address<T>(address: usize) -> Result<T, AssembleErrorKind>
+where
+    T: FromPrimitive,
+{
+    T::from_usize(address).ok_or(AssembleErrorKind::AddressOverflow(address))
+}
@@ -0,0 +1,182 @@
+//! Render the contents of a [Memory] as human-readable subleq instructions.
+
+use core::ops::Range;
+use std::fmt::{Display, Write};
+
+use crate::{
+    InstrKind, Instruction, Memory, Word, classify_instruction, is_halt_instruction, word,
+};
+
+/// A single disassembled instruction, see [disassemble_iter].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct DisasmLine<T> {
+    /// The address of the instruction.
+    pub address: T,
+    /// The argument A.
+    pub a: T,
+    /// The argument B.
+    pub b: T,
+    /// The argument C.
+    pub c: T,
+    /// Whether the instruction is the halt idiom, see [is_halt_instruction].
+    pub is_halt: bool,
+    /// Whether A equals B, so the instruction clears the cell and always jumps to C.
+    pub is_self_clear: bool,
+    /// What the instruction does, see [classify_instruction].
+    pub kind: InstrKind,
+}
+
+/// Disassemble `count` instructions starting at address `start` into [DisasmLine]s.
+///
+/// Every instruction is read on its own, so an instruction which can't be read
+/// yields an error and the iteration continues with the next one.
+///
+/// ```
+/// # use qelbus::{disasm::disassemble_iter, memory::LinearMemory};
+/// let memory = LinearMemory::<i32, 16>::from_slice(&[6, 7, 3, 8, 8, -1, 1, 2, 0]).unwrap();
+/// let halts: Vec<_> = disassemble_iter(&memory, 0, 2)
+///     .filter_map(Result::ok)
+///     .filter(|line| line.is_halt)
+///     .map(|line| line.address)
+///     .collect();
+/// assert_eq!(halts, [3]);
+/// ```
+pub fn disassemble_iter<T, M>(
+    memory: &M,
+    start: T,
+    count: usize,
+) -> impl Iterator<Item = Result<DisasmLine<T>, M::Error>>
+where
+    T: Word,
+    M: Memory<T>,
+{
+    let mut address = start;
+    (0..count).map(move |_| {
+        let line = memory
+            .instruction(&address)
+            .map(|Instruction { a, b, c }| DisasmLine {
+                address: address.clone(),
+                is_halt: is_halt_instruction(&a, &b, &c),
+                is_self_clear: a == b,
+                kind: classify_instruction(&address, &a, &b, &c),
+                a,
+                b,
+                c,
+            });
+        address = address.wrapping_add(&word::three());
+        line
+    })
+}
+
+/// Disassemble `count` instructions starting at address `start`.
+///
+/// Every instruction is rendered on its own line as `address: A B C`.
+/// Instructions where A equals B clear the cell and always jump to C,
+/// which is annotated with a `; clear A` comment, followed by `, jump C` unless C is the next instruction,
+/// or with `; halt` if C is a halt address, see [classify_instruction].
+///
+/// ```
+/// # use qelbus::{disasm::disassemble, memory::LinearMemory};
+/// let memory = LinearMemory::<i32, 16>::from_slice(&[6, 7, 3, 8, 8, -1, 2, 2, 0]).unwrap();
+/// assert_eq!(
+///     disassemble(&memory, 0, 3).unwrap(),
+///     "0: 6 7 3\n3: 8 8 -1 ; halt\n6: 2 2 0 ; clear 2, jump 0\n",
+/// );
+/// ```
+///
+/// # Errors
+/// Returns an [Memory::Error] when getting an instruction from the [Memory] fails.
+pub fn disassemble<T, M>(memory: &M, start: T, count: usize) -> Result<String, M::Error>
+where
+    T: Word + Display,
+    M: Memory<T>,
+{
+    let mut listing = String::new();
+
+    for line in disassemble_iter(memory, start, count) {
+        let DisasmLine {
+            address,
+            a,
+            b,
+            c,
+            kind,
+            ..
+        } = line?;
+
+        // Writing to a String never fails.
+        let _ = write!(listing, "{address}: {a} {b} {c}");
+        match kind {
+            InstrKind::Halt => listing.push_str(" ; halt"),
+            InstrKind::Clear if c == address.wrapping_add(&word::three()) => {
+                let _ = write!(listing, " ; clear {a}");
+            }
+            InstrKind::Clear => {
+                let _ = write!(listing, " ; clear {a}, jump {c}");
+            }
+            InstrKind::Branch | InstrKind::Sub => {}
+        }
+        listing.push('\n');
+    }
+
+    Ok(listing)
+}
+
+/// The instructions of a code region which write into it, see [analyze_writes].
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct WriteAnalysis<T> {
+    /// The address and the argument B of every instruction whose B lies in the code region,
+    /// ordered by address.
+    pub code_writes: Vec<(T, T)>,
+}
+
+impl<T> WriteAnalysis<T> {
+    /// Whether any instruction of the code region writes into it.
+    pub fn is_self_modifying(&self) -> bool {
+        !self.code_writes.is_empty()
+    }
+}
+
+/// Find the instructions in `code` whose argument B, the address they write to, lies in `code`.
+///
+/// The instructions are decoded every three cells from the start of `code`, like [disassemble_iter].
+/// If none of them writes into `code`, running the program can't change its code
+/// as long as execution stays within `code` and only executes these instructions,
+/// so it is safe to cache them, see [CachedMemory](crate::memory::CachedMemory).
+/// This is a static check of the arguments as they are now:
+/// it can't tell where execution goes, so a jump into data or into the middle of an instruction
+/// or a memory which maps addresses to other cells can still modify the code.
+///
+/// ```
+/// # use qelbus::{disasm::analyze_writes, memory::LinearMemory};
+/// // 6 7 3 writes to the data at 7, 8 8 -1 writes to the data at 8.
+/// let memory = LinearMemory::<i32, 16>::from_slice(&[6, 7, 3, 8, 8, -1, 2, 5, 0]).unwrap();
+/// assert!(!analyze_writes(&memory, 0..6).unwrap().is_self_modifying());
+///
+/// // 6 5 3 writes to C of the second instruction.
+/// let memory = LinearMemory::<i32, 16>::from_slice(&[6, 5, 3, 8, 8, 1, 2]).unwrap();
+/// assert_eq!(analyze_writes(&memory, 0..6).unwrap().code_writes, [(0, 5)]);
+/// ```
+///
+/// # Errors
+/// Returns an [Memory::Error] when getting an instruction from the [Memory] fails.
+pub fn analyze_writes<T, M>(memory: &M, code: Range<T>) -> Result<WriteAnalysis<T>, M::Error>
+where
+    T: Word,
+    M: Memory<T>,
+{
+    let mut code_writes = Vec::new();
+    let mut address = code.start.clone();
+
+    while address < code.end {
+        let Instruction { b, .. } = memory.instruction(&address)?;
+        if code.contains(&b) {
+            code_writes.push((address.clone(), b));
+        }
+        match address.checked_add(&word::three()) {
+            Some(next) => address = next,
+            None => break,
+        }
+    }
+
+    Ok(WriteAnalysis { code_writes })
+}
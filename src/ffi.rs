@@ -0,0 +1,149 @@
+//! C bindings for embedding the interpreter in non-Rust programs.
+//!
+//! Enabled by the `ffi` feature. The machine has 32-bit words and a
+//! [LinearMemory] of 65536 cells. To build a shared library, run
+//! `cargo rustc --release --features ffi --crate-type cdylib`.
+//!
+//! Functions which return an `int` status use these codes:
+//!
+//! | code | meaning |
+//! |------|---------|
+//! | `0`  | success, for [subleq_step] the program continues |
+//! | `1`  | [subleq_step] only: the program has halted |
+//! | `-1` | [Error::AddressOutOfRange] |
+//! | `-2` | [Error::NegativeAddress] |
+//! | `-3` | [Error::ImmutableAddress] |
+//! | `-4` | a pointer argument is null |
+//! | `-5` | [Error::Overflow] |
+//! | `-6` | [Error::Custom] |
+//! | `-7` | [Error::ProgramTooLarge] |
+//! | `-8` | [Error::InstructionPointerOutOfBounds] |
+//!
+//! ```
+//! # use qelbus::ffi::{subleq_free, subleq_get, subleq_new, subleq_step};
+//! let program = [3, 3, -1];
+//! unsafe {
+//!     let subleq = subleq_new(program.as_ptr(), program.len());
+//!     assert_eq!(subleq_step(subleq), 1);
+//!
+//!     let mut value = 0;
+//!     assert_eq!(subleq_get(subleq, 0, &mut value), 0);
+//!     assert_eq!(value, 3);
+//!     assert_eq!(subleq_get(subleq, -1, &mut value), -2);
+//!
+//!     subleq_free(subleq);
+//! }
+//! ```
+
+use core::ffi::c_int;
+
+use crate::{
+    Memory, StepOutcome, Subleq,
+    memory::{Error, LinearMemory},
+};
+
+/// The amount of cells in the memory of a [FfiSubleq].
+const SIZE: usize = 1 << 16;
+
+/// The machine created by [subleq_new].
+pub type FfiSubleq = Subleq<i32, LinearMemory<i32, SIZE>>;
+
+/// The status code for a null pointer argument.
+const NULL_POINTER: c_int = -4;
+
+/// Convert an [Error] into its status code.
+fn error_code(error: Error) -> c_int {
+    match error {
+        Error::AddressOutOfRange(_) => -1,
+        Error::NegativeAddress(_) => -2,
+        Error::ImmutableAddress(_) => -3,
+        Error::Overflow { .. } => -5,
+        Error::Custom(_) => -6,
+        Error::ProgramTooLarge { .. } => -7,
+        Error::InstructionPointerOutOfBounds(_) => -8,
+    }
+}
+
+/// Create a new machine with `len` cells of `program` loaded at the start of its memory.
+///
+/// Returns null if `program` is null or doesn't fit in the memory.
+/// The machine has to be freed with [subleq_free].
+///
+/// # Safety
+/// `program` has to point to `len` consecutive, initialized `int32_t` values.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn subleq_new(program: *const i32, len: usize) -> *mut FfiSubleq {
+    if program.is_null() || len > SIZE {
+        return core::ptr::null_mut();
+    }
+
+    // SAFETY: the caller guarantees that `program` points to `len` values.
+    let program = unsafe { core::slice::from_raw_parts(program, len) };
+    Box::into_raw(Box::new(Subleq::with_program(program)))
+}
+
+/// Execute the current instruction of a machine.
+///
+/// Returns 0 if the program continues, 1 if it has halted or a negative error code.
+///
+/// # Safety
+/// `subleq` has to be null or a pointer returned by [subleq_new] which hasn't been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn subleq_step(subleq: *mut FfiSubleq) -> c_int {
+    // SAFETY: the caller guarantees that a non-null `subleq` is valid.
+    let Some(subleq) = (unsafe { subleq.as_mut() }) else {
+        return NULL_POINTER;
+    };
+
+    match subleq.step() {
+        Ok(StepOutcome::Continued) => 0,
+        Ok(StepOutcome::Halted) => 1,
+        Ok(StepOutcome::IpOutOfBounds) => error_code(Error::InstructionPointerOutOfBounds(
+            subleq.curr_instruction.into(),
+        )),
+        Err(error) => error_code(error),
+    }
+}
+
+/// Read the value at `address` of a machine into `out`.
+///
+/// Returns 0 on success or a negative error code.
+///
+/// # Safety
+/// `subleq` has to be null or a pointer returned by [subleq_new] which hasn't been freed.
+/// `out` has to be null or valid for writing an `int32_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn subleq_get(
+    subleq: *const FfiSubleq,
+    address: i32,
+    out: *mut i32,
+) -> c_int {
+    // SAFETY: the caller guarantees that a non-null `subleq` is valid.
+    let Some(subleq) = (unsafe { subleq.as_ref() }) else {
+        return NULL_POINTER;
+    };
+    if out.is_null() {
+        return NULL_POINTER;
+    }
+
+    match subleq.memory.get(&address) {
+        Ok(value) => {
+            // SAFETY: the caller guarantees that a non-null `out` is valid for writes.
+            unsafe { out.write(*value) };
+            0
+        }
+        Err(error) => error_code(error),
+    }
+}
+
+/// Free a machine created by [subleq_new]. Does nothing if `subleq` is null.
+///
+/// # Safety
+/// `subleq` has to be null or a pointer returned by [subleq_new] which hasn't been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn subleq_free(subleq: *mut FfiSubleq) {
+    if !subleq.is_null() {
+        // SAFETY: the caller guarantees that `subleq` was created by `subleq_new`.
+        drop(unsafe { Box::from_raw(subleq) });
+    }
+}
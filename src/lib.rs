@@ -1,6 +1,7 @@
 //! Customizable [subleq](https://esolangs.org/wiki/Subleq) instruction set program execution.
 //!
-//! Provides a subleq interpreter and a trait to customize memory mappings.
+//! Provides a subleq interpreter, a trait to customize memory mappings
+//! and some ready-made [memory] implementations.
 //!
 //! Subleq is an instruction set which contains only one instruction: subleq.
 //! A subleq computer has a single memory unit in which both the program and its data is stored.
@@ -19,7 +20,7 @@
 //! ```
 //!
 //! ```no_run
-//! # use qelbus::{Memory, Subleq};
+//! # use qelbus::{Memory, StepOutcome, Subleq};
 //! struct ByteMemory([i8; 256]);
 //!
 //! impl Memory<i8> for ByteMemory {
@@ -45,8 +46,21 @@
 //! // <initialize memory with a program>
 //! let mut subleq = Subleq::new(memory);
 //!
-//! while let Ok(_) = subleq.step() { }
+//! while subleq.step().unwrap() == StepOutcome::Continued { }
 //! ```
+//!
+//! # Features
+//! - `std` (enabled by default): everything which needs an allocator, like [memory::SparseMemory],
+//!   the assembler, the macro assembler, the disassembler, breakpoints, the execution history,
+//!   the debugging [session] and the [scheduler] for multiple threads.
+//!   Without it the crate is `no_std`.
+//! - `serde`: implement `Serialize` and `Deserialize` for [Subleq] and [memory::LinearMemory].
+//! - `wasm`: JavaScript bindings through `wasm-bindgen`, see `wasm::WasmSubleq`.
+//! - `ffi`: C bindings, see the `ffi` module.
+//! - `tokio`: [Subleq::run_async], which yields to the tokio runtime between chunks of instructions.
+//! - `bigint`: [Word] for `num::BigInt`, which runs programs on arbitrary-precision integers without overflow.
+//! - `mmap`: `memory::MmapMemory`, which stores the cells in a memory-mapped file.
+#![cfg_attr(not(feature = "std"), no_std)]
 #![deny(
     missing_docs,
     clippy::missing_docs_in_private_items,
@@ -55,10 +69,26 @@
     clippy::missing_safety_doc
 )]
 
-use num::{
-    Signed,
-    traits::{WrappingAdd, WrappingSub},
-};
+#[cfg(feature = "std")]
+pub mod asm;
+#[cfg(feature = "std")]
+pub mod disasm;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod memory;
+#[cfg(feature = "std")]
+pub mod scheduler;
+#[cfg(feature = "std")]
+pub mod session;
+#[cfg(feature = "std")]
+pub mod subasm;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+mod word;
+
+pub use word::Word;
+
+use num::traits::AsPrimitive;
 
 /// Interpret a subleq program stored inside a [Memory].
 ///
@@ -77,23 +107,95 @@ use num::{
 ///     jump curr_instruction + 3
 /// }
 /// ```
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+///
+/// With the `serde` feature enabled, [Subleq] implements `Serialize` and `Deserialize`
+/// when its word type and [Memory] do, which allows saving and restoring a running machine.
+///
+/// The `Debug` output shows the instruction pointer together with the instruction at it,
+/// or `<unreadable>` if the instruction can't be fetched, instead of the contents of the memory.
+///
+/// ```
+/// # use qelbus::{Subleq, memory::LinearMemory};
+/// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[6, 7, 3, 8, 8, -1, 2, 5]);
+/// subleq.step().unwrap();
+/// assert_eq!(
+///     format!("{subleq:?}"),
+///     "Subleq { curr_instruction: 3, next_instruction: 8 8 -1, cycles: 1, halted: false, .. }",
+/// );
+/// ```
+#[derive(Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Subleq<T, M>
 where
-    T: Signed + WrappingAdd + WrappingSub + From<i8> + Copy,
+    T: Word,
     M: Memory<T>,
 {
     /// The memory that the subleq program is stored in.
     pub memory: M,
     /// The address of the first argument of the instruction which is going to be executed next.
     pub curr_instruction: T,
+    /// The address execution starts at, which [Self::reset] returns to.
+    entry: T,
+    /// The amount of instructions which have been executed.
+    cycles: u64,
+    /// The amount of executed instructions which branched to C.
+    branches_taken: u64,
+    /// The amount of executed instructions which continued with the next instruction.
+    branches_not_taken: u64,
+    /// Whether a halt condition has been detected, see [Self::halted].
+    halted: bool,
+    /// How instructions are decoded and where they branch to, see [Self::set_mode].
+    mode: Mode<T>,
+    /// What happens when the subtraction of an instruction overflows, see [Self::set_overflow_mode].
+    overflow_mode: OverflowMode,
+    /// The value results are compared against instead of the test of the word type, see [Self::set_branch_threshold].
+    branch_threshold: Option<T>,
+    /// The highest address the instruction pointer may reach, see [Self::set_max_ip].
+    max_ip: Option<T>,
+    /// The predicate which decides whether the program halts, see [Self::set_halt_predicate].
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    halt_predicate: Option<HaltPredicate<T>>,
+    /// The handler which [Self::run] calls when the program halts, see [Self::set_halt_handler].
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    halt_handler: Option<HaltHandler<T, M>>,
+    /// The addresses at which [Self::run_to_breakpoint] stops.
+    #[cfg(feature = "std")]
+    breakpoints: Vec<T>,
+    /// The addresses whose writes make [Self::run_to_breakpoint] stop.
+    #[cfg(feature = "std")]
+    watchpoints: Vec<T>,
+    /// The most recent changes made by executed instructions, oldest first.
+    #[cfg(feature = "std")]
+    history: std::collections::VecDeque<Change<T>>,
+    /// The maximum amount of changes stored in [Self::history].
+    #[cfg(feature = "std")]
+    history_capacity: usize,
+    /// Whether executed instructions are counted in [Self::profile].
+    #[cfg(feature = "std")]
+    profiling: bool,
+    /// The amount of times the instruction at each address has been executed.
+    #[cfg(feature = "std")]
+    profile: std::collections::BTreeMap<T, u64>,
+    /// Whether executed instructions are recorded in [Self::coverage].
+    #[cfg(feature = "std")]
+    covering: bool,
+    /// The addresses of the instructions which have been executed.
+    #[cfg(feature = "std")]
+    coverage: std::collections::BTreeSet<T>,
+    /// The memory which [Self::reset] restores, see [Self::save_initial_memory].
+    ///
+    /// Boxed so saving a large memory doesn't double the size of the machine.
+    #[cfg(feature = "std")]
+    initial_memory: Option<Box<M>>,
     #[doc(hidden)]
-    _marker: std::marker::PhantomData<T>,
+    _marker: core::marker::PhantomData<T>,
 }
 
 impl<T, M> Default for Subleq<T, M>
 where
-    T: Signed + WrappingAdd + WrappingSub + From<i8> + Copy,
+    T: Word,
     M: Memory<T> + Default,
 {
     fn default() -> Self {
@@ -101,9 +203,30 @@ where
     }
 }
 
+impl<T, M> core::fmt::Debug for Subleq<T, M>
+where
+    T: Word + core::fmt::Debug,
+    M: Memory<T>,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut debug = f.debug_struct("Subleq");
+        debug.field("curr_instruction", &self.curr_instruction);
+        match self.fetch() {
+            Ok(Instruction { a, b, c }) => {
+                debug.field("next_instruction", &format_args!("{a:?} {b:?} {c:?}"))
+            }
+            Err(_) => debug.field("next_instruction", &format_args!("<unreadable>")),
+        };
+        debug
+            .field("cycles", &self.cycles)
+            .field("halted", &self.halted)
+            .finish_non_exhaustive()
+    }
+}
+
 impl<T, M> Subleq<T, M>
 where
-    T: Signed + WrappingAdd + WrappingSub + From<i8> + Copy,
+    T: Word,
     M: Memory<T>,
 {
     /// Construct a new [Subleq] struct from a [Memory].
@@ -133,10 +256,254 @@ where
     /// let subleq = Subleq::new(memory);
     /// ```
     pub fn new(memory: M) -> Self {
+        Self::new_at(memory, T::zero())
+    }
+
+    /// Construct a new [Subleq] struct from a [Memory] that starts execution at `entry`.
+    ///
+    /// This is useful for programs whose code doesn't start at the first address,
+    /// for example because a data section precedes it.
+    ///
+    /// ```
+    /// # use qelbus::{Memory, Subleq, memory::LinearMemory};
+    /// // Data in the first three cells, followed by the code.
+    /// let memory = LinearMemory::<i32, 16>::from_slice(&[5, 2, 0, 1, 0, 6, 2, 2, -1]).unwrap();
+    /// let mut subleq = Subleq::new_at(memory, 3);
+    /// subleq.run().unwrap();
+    /// assert_eq!(subleq.memory.get(&0), Ok(&3));
+    /// ```
+    pub fn new_at(memory: M, entry: T) -> Self {
         Self {
             memory,
-            curr_instruction: T::zero(),
-            _marker: std::marker::PhantomData,
+            curr_instruction: entry.clone(),
+            entry,
+            cycles: 0,
+            branches_taken: 0,
+            branches_not_taken: 0,
+            halted: false,
+            mode: Mode::Subleq3,
+            overflow_mode: OverflowMode::Wrap,
+            branch_threshold: None,
+            max_ip: None,
+            #[cfg(feature = "std")]
+            halt_predicate: None,
+            #[cfg(feature = "std")]
+            halt_handler: None,
+            #[cfg(feature = "std")]
+            breakpoints: Vec::new(),
+            #[cfg(feature = "std")]
+            watchpoints: Vec::new(),
+            #[cfg(feature = "std")]
+            history: std::collections::VecDeque::new(),
+            #[cfg(feature = "std")]
+            history_capacity: 0,
+            #[cfg(feature = "std")]
+            profiling: false,
+            #[cfg(feature = "std")]
+            profile: std::collections::BTreeMap::new(),
+            #[cfg(feature = "std")]
+            covering: false,
+            #[cfg(feature = "std")]
+            coverage: std::collections::BTreeSet::new(),
+            #[cfg(feature = "std")]
+            initial_memory: None,
+            _marker: core::marker::PhantomData,
+        }
+    }
+
+    /// Set the instruction pointer, which makes the instruction at `addr` the next one to be executed.
+    ///
+    /// This also resumes a machine which has [halted](Self::halted).
+    ///
+    /// ```
+    /// # use qelbus::{Subleq, memory::LinearMemory};
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[3, 3, -1, 6, 6, -1]);
+    /// subleq.run().unwrap();
+    ///
+    /// subleq.set_ip(3);
+    /// assert!(!subleq.halted());
+    /// subleq.run().unwrap();
+    /// assert_eq!(subleq.cycles(), 2);
+    /// ```
+    pub fn set_ip(&mut self, addr: T) {
+        self.curr_instruction = addr;
+        self.halted = false;
+    }
+
+    /// Get the arguments A, B and C of the current instruction without executing it.
+    ///
+    /// ```
+    /// # use qelbus::{Subleq, memory::LinearMemory};
+    /// let subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[3, 3, -1]);
+    /// assert_eq!(subleq.peek_instruction().unwrap(), (3, 3, -1));
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an [Memory::Error] when getting the instruction from [Memory] fails.
+    /// The error type is specific to the [Memory] implementation.
+    pub fn peek_instruction(&self) -> Result<(T, T, T), M::Error> {
+        let instruction = self.fetch()?;
+        Ok((instruction.a, instruction.b, instruction.c))
+    }
+
+    /// Get the current execution [Mode].
+    pub fn mode(&self) -> Mode<T> {
+        self.mode.clone()
+    }
+
+    /// Set the execution [Mode], which selects how instructions are decoded and where they branch to.
+    ///
+    /// The default is [Mode::Subleq3].
+    ///
+    /// ```
+    /// # use qelbus::{Memory, Mode, Subleq, memory::LinearMemory};
+    /// // Two argument instructions which branch to the halt address when the result is not positive.
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[4, 5, 5, 5, 3, 4]);
+    /// subleq.set_mode(Mode::Subleq2 { target: -1 });
+    /// assert_eq!(subleq.peek_instruction().unwrap(), (4, 5, -1));
+    ///
+    /// subleq.run().unwrap();
+    /// assert_eq!(subleq.memory.get(&5), Ok(&0));
+    /// assert_eq!(subleq.cycles(), 2);
+    /// ```
+    pub fn set_mode(&mut self, mode: Mode<T>) {
+        self.mode = mode;
+    }
+
+    /// Get the current [OverflowMode].
+    pub fn overflow_mode(&self) -> OverflowMode {
+        self.overflow_mode
+    }
+
+    /// Set what happens when the subtraction of an instruction overflows the word type.
+    ///
+    /// The default is [OverflowMode::Wrap].
+    ///
+    /// ```
+    /// # use qelbus::{Memory, OverflowMode, Subleq, memory::{Error, LinearMemory}};
+    /// // -100 - 100 overflows an i8.
+    /// let program = [3, 4, -1, 100, -100];
+    ///
+    /// let mut subleq = Subleq::<i8, LinearMemory<i8, 16>>::with_program(&program);
+    /// subleq.step().unwrap();
+    /// assert_eq!(subleq.memory.get(&4), Ok(&56));
+    ///
+    /// let mut subleq = Subleq::<i8, LinearMemory<i8, 16>>::with_program(&program);
+    /// subleq.set_overflow_mode(OverflowMode::Saturate);
+    /// subleq.step().unwrap();
+    /// assert_eq!(subleq.memory.get(&4), Ok(&-128));
+    ///
+    /// let mut subleq = Subleq::<i8, LinearMemory<i8, 16>>::with_program(&program);
+    /// subleq.set_overflow_mode(OverflowMode::Error);
+    /// assert_eq!(subleq.step(), Err(Error::Overflow { address: 0, a: 100, b: -100 }));
+    /// assert_eq!(subleq.memory.get(&4), Ok(&-100));
+    /// ```
+    pub fn set_overflow_mode(&mut self, mode: OverflowMode) {
+        self.overflow_mode = mode;
+    }
+
+    /// Get the threshold set with [Self::set_branch_threshold].
+    pub fn branch_threshold(&self) -> Option<&T> {
+        self.branch_threshold.as_ref()
+    }
+
+    /// Make instructions branch when their result is less than or equal to `threshold`,
+    /// for variants of subleq which compare against a value other than 0.
+    ///
+    /// With `None`, which is the default, instructions branch according to the word type, see [Word].
+    /// A threshold is compared against the result stored at B,
+    /// so for unsigned words a subtraction which wraps around only branches if its stored result is small enough.
+    ///
+    /// ```
+    /// # use qelbus::{Memory, Subleq, memory::LinearMemory};
+    /// // 3 - 1 = 2 is positive, so the instruction doesn't branch to -1 by default.
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[3, 4, -1, 1, 3]);
+    /// subleq.step().unwrap();
+    /// assert_eq!(subleq.curr_instruction, 3);
+    ///
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[3, 4, -1, 1, 3]);
+    /// subleq.set_branch_threshold(Some(2));
+    /// subleq.step().unwrap();
+    /// assert!(subleq.halted());
+    /// ```
+    pub fn set_branch_threshold(&mut self, threshold: Option<T>) {
+        self.branch_threshold = threshold;
+    }
+
+    /// Get the maximum set with [Self::set_max_ip].
+    pub fn max_ip(&self) -> Option<&T> {
+        self.max_ip.as_ref()
+    }
+
+    /// Refuse to execute instructions at addresses above `max`,
+    /// to catch programs whose control flow went wrong, especially on unbounded memories.
+    ///
+    /// With `None`, which is the default, the instruction pointer isn't limited.
+    /// Stepping with the instruction pointer above the maximum returns the error from
+    /// [Memory::instruction_pointer_error] before anything is changed,
+    /// like [Error::InstructionPointerOutOfBounds](memory::Error::InstructionPointerOutOfBounds).
+    /// For memories which can't represent this error stepping returns [StepOutcome::IpOutOfBounds] instead,
+    /// without halting the machine, and running returns [RunResult::IpOutOfBounds].
+    ///
+    /// ```
+    /// # use qelbus::{Subleq, memory::{Error, LinearMemory, Protected}};
+    /// // Jumps far past the end of the program.
+    /// let memory = LinearMemory::<i32, 16>::from_slice(&[3, 3, 1000]).unwrap();
+    /// let mut subleq = Subleq::new(memory);
+    /// subleq.set_max_ip(Some(3));
+    /// subleq.step().unwrap();
+    /// assert_eq!(subleq.step(), Err(Error::InstructionPointerOutOfBounds(1000)));
+    /// assert_eq!(subleq.curr_instruction, 1000);
+    ///
+    /// // Wrappers pass the error of the inner memory on.
+    /// let memory = LinearMemory::<i32, 16>::from_slice(&[3, 3, 9]).unwrap();
+    /// let mut subleq = Subleq::new(Protected::new(memory, 0..3));
+    /// subleq.set_max_ip(Some(3));
+    /// subleq.step().unwrap();
+    /// assert_eq!(subleq.step(), Err(Error::InstructionPointerOutOfBounds(9)));
+    /// ```
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")] {
+    /// # use qelbus::{Memory, RunReport, RunResult, StepOutcome, Subleq, memory::SparseMemory};
+    /// // A sparse memory can't fail, so a runaway program is reported by the outcome.
+    /// let mut memory = SparseMemory::new();
+    /// memory.set_range(&0, &[3, 3, 1000]).unwrap();
+    /// let mut subleq = Subleq::new(memory);
+    /// subleq.set_max_ip(Some(3));
+    /// assert_eq!(subleq.run().unwrap(), RunReport { steps: 1, reason: RunResult::IpOutOfBounds });
+    /// assert_eq!(subleq.step().unwrap(), StepOutcome::IpOutOfBounds);
+    /// assert!(!subleq.halted());
+    /// # }
+    /// ```
+    pub fn set_max_ip(&mut self, max: Option<T>) {
+        self.max_ip = max;
+    }
+
+    /// Check whether the instruction pointer lies above the maximum set with [Self::set_max_ip].
+    fn ip_out_of_bounds(&self) -> bool {
+        self.above_max_ip(&self.curr_instruction)
+    }
+
+    /// Check whether `address` lies above the maximum set with [Self::set_max_ip].
+    fn above_max_ip(&self, address: &T) -> bool {
+        self.max_ip.as_ref().is_some_and(|max| address > max)
+    }
+
+    /// Decode the instruction at the instruction pointer according to the current [Mode].
+    fn fetch(&self) -> Result<Instruction<T>, M::Error> {
+        self.fetch_at(&self.curr_instruction)
+    }
+
+    /// Fetch the instruction at `address` according to the [Mode].
+    fn fetch_at(&self, address: &T) -> Result<Instruction<T>, M::Error> {
+        match &self.mode {
+            Mode::Subleq3 => self.memory.instruction(address),
+            Mode::Subleq2 { target } => Ok(Instruction {
+                a: self.memory.get(address)?.clone(),
+                b: self.memory.get(&address.wrapping_add(&T::one()))?.clone(),
+                c: target.clone(),
+            }),
         }
     }
 
@@ -147,8 +514,13 @@ where
     /// 2. LEQ: if the above result is less than or equal to 0,
     ///    set the instruction pointer to address C. Otherwise set it to the next instruction.
     ///
-    /// ```no_run
-    /// # use qelbus::{Subleq, Memory};
+    /// Returns [StepOutcome::Halted] when the instruction jumps to a halt address,
+    /// see [Word::is_halt_address].
+    /// Once the machine has halted, stepping does nothing and keeps returning [StepOutcome::Halted],
+    /// see [Self::halted].
+    ///
+    /// ```
+    /// # use qelbus::{Subleq, Memory, StepOutcome};
     /// # struct ByteMemory([i8; 256]);
     /// #
     /// # impl Memory<i8> for ByteMemory {
@@ -165,96 +537,2710 @@ where
     /// # impl ByteMemory {
     /// #   fn new() -> Self { Self([0; 256]) }
     /// # }
-    /// let memory = ByteMemory::new();
+    /// let mut memory = ByteMemory::new();
+    /// memory.0[..6].copy_from_slice(&[6, 6, 3, 6, 6, -1]);
+    ///
+    /// let mut subleq = Subleq::new(memory);
+    /// assert_eq!(subleq.step().unwrap(), StepOutcome::Continued);
+    /// assert_eq!(subleq.step().unwrap(), StepOutcome::Halted);
+    /// ```
+    ///
+    /// The branch depends on the value of B after the subtraction, not before it.
+    /// ```
+    /// # use qelbus::{Subleq, Memory, StepOutcome};
+    /// # struct ByteMemory([i8; 256]);
+    /// #
+    /// # impl Memory<i8> for ByteMemory {
+    /// #   type Error = std::convert::Infallible;
+    /// #
+    /// #   fn get(&self, index: &i8) -> Result<&i8, Self::Error> {
+    /// #     Ok(&self.0[*index as u8 as usize])
+    /// #   }
+    /// #   fn set(&mut self, index: &i8, value: i8) -> Result<(), Self::Error> {
+    /// #     self.0[*index as u8 as usize] = value;
+    /// #     Ok(())
+    /// #   }
+    /// # }
+    /// # impl ByteMemory {
+    /// #   fn new() -> Self { Self([0; 256]) }
+    /// # }
+    /// let mut memory = ByteMemory::new();
+    /// // B starts out positive, but 1 - 1 = 0 so the jump is taken.
+    /// memory.0[..5].copy_from_slice(&[3, 4, -1, 1, 1]);
+    ///
     /// let mut subleq = Subleq::new(memory);
-    /// while let Ok(_) = subleq.step() { }
+    /// assert_eq!(subleq.step().unwrap(), StepOutcome::Halted);
+    /// assert_eq!(subleq.memory.0[4], 0);
     /// ```
     ///
     /// # Errors
     /// Returns an [Memory::Error] when getting or setting [Memory] fails.
     /// The error type is specific to the [Memory] implementation.
-    pub fn step(&mut self) -> Result<(), M::Error> {
-        let instruction = self.memory.instruction(&self.curr_instruction)?;
-
-        let (a_value, b_value) = (
-            self.memory.get(instruction.a)?,
-            self.memory.get(instruction.b)?,
-        );
+    #[inline]
+    pub fn step(&mut self) -> Result<StepOutcome, M::Error> {
+        self.execute(|_| {})
+    }
 
-        let result = b_value.wrapping_sub(a_value);
+    /// Execute one instruction like [Self::step] and add 1 to `steps` if an instruction was executed.
+    ///
+    /// Only the cycles around this step are compared, so the count stays right
+    /// when a halt handler resets the cycles between steps.
+    pub(crate) fn step_counted(&mut self, steps: &mut u64) -> Result<StepOutcome, M::Error> {
+        let cycles = self.cycles;
+        let outcome = self.step()?;
+        if self.cycles != cycles {
+            *steps += 1;
+        }
+        Ok(outcome)
+    }
 
-        if !result.is_positive() {
-            self.curr_instruction = *instruction.c;
-        } else {
-            self.curr_instruction = self.curr_instruction.wrapping_add(&T::from(3i8));
+    /// Execute the current instruction like [Self::step], but check that A and B can be read
+    /// and that C is a halt address or can be read before anything is changed.
+    ///
+    /// On an error the machine is left untouched, so the error can be recovered from,
+    /// for example by fixing the memory and stepping again.
+    /// [Self::step] rejects an invalid C only when jumping to it fetches the next instruction,
+    /// after the result has been stored.
+    ///
+    /// Whether B can be written to can't be checked up front,
+    /// but the result is stored before the instruction pointer is updated,
+    /// so a failing write doesn't change the state either.
+    /// Memories which change state when loading, like [IoMemory](memory::IoMemory), still do so.
+    ///
+    /// ```
+    /// # use qelbus::{Memory, Subleq, memory::{Error, LinearMemory}};
+    /// // C lies outside of the memory, but the instruction doesn't branch.
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[3, 4, 20, 0, 1]);
+    /// assert_eq!(subleq.checked_step(), Err(Error::AddressOutOfRange(20)));
+    /// assert_eq!(subleq.memory.get(&4), Ok(&1));
+    /// assert_eq!(subleq.curr_instruction, 0);
+    ///
+    /// subleq.step().unwrap();
+    /// assert_eq!(subleq.curr_instruction, 3);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an [Memory::Error] when getting or setting [Memory] fails.
+    /// The error type is specific to the [Memory] implementation.
+    pub fn checked_step(&mut self) -> Result<StepOutcome, M::Error> {
+        if !self.halted && !self.curr_instruction.is_halt_address() && !self.ip_out_of_bounds() {
+            let Instruction { a, b, c } = self.fetch()?;
+            self.memory.get(&a)?;
+            self.memory.get(&b)?;
+            if !c.is_halt_address() {
+                self.memory.get(&c)?;
+            }
         }
+        self.step()
+    }
 
-        let b_copy = *instruction.b;
-        self.memory.set(&b_copy, result)?;
-        Ok(())
+    /// Execute the current instruction like [Self::step], calling `hook` before the result is stored.
+    ///
+    /// The hook receives the address of the instruction, its arguments A, B and C
+    /// and the result of the subtraction which is going to be stored at B.
+    ///
+    /// ```
+    /// # use qelbus::{Subleq, memory::LinearMemory};
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[6, 7, 3, 8, 8, -1, 2, 5]);
+    ///
+    /// let mut trace = Vec::new();
+    /// while !subleq.curr_instruction.is_negative() {
+    ///     subleq.step_traced(|address, instruction, result| trace.push((address, instruction, result))).unwrap();
+    /// }
+    /// assert_eq!(trace, [(0, (6, 7, 3), 3), (3, (8, 8, -1), 0)]);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an [Memory::Error] when getting or setting [Memory] fails.
+    /// The error type is specific to the [Memory] implementation.
+    pub fn step_traced<F>(&mut self, mut hook: F) -> Result<StepOutcome, M::Error>
+    where
+        F: FnMut(T, (T, T, T), T),
+    {
+        self.execute(|execution| {
+            let Instruction { a, b, c } = execution.instruction.clone();
+            hook(
+                execution.address.clone(),
+                (a, b, c),
+                execution.result.clone(),
+            )
+        })
     }
-}
 
-/// Represent an instruction.
-pub struct Instruction<'a, T> {
-    /// The subtrahend address.
-    pub a: &'a T,
-    /// The minuend and store address.
-    pub b: &'a T,
-    /// The address to jump to.
-    pub c: &'a T,
-}
+    /// Execute the current instruction like [Self::step],
+    /// calling `hook` with the written address if the instruction writes to one of its own cells.
+    ///
+    /// Self-modification is still allowed, the hook is called before the value is stored.
+    /// This makes it possible to log or flag programs which unintentionally overwrite their code.
+    ///
+    /// ```
+    /// # use qelbus::{Subleq, memory::LinearMemory};
+    /// // The first instruction clears its own argument C.
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[2, 2, 3, 6, 6, -1]);
+    ///
+    /// let mut modified = Vec::new();
+    /// while !subleq.halted() {
+    ///     subleq.step_detecting_self_modification(|address| modified.push(address)).unwrap();
+    /// }
+    /// assert_eq!(modified, [2]);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an [Memory::Error] when getting or setting [Memory] fails.
+    /// The error type is specific to the [Memory] implementation.
+    pub fn step_detecting_self_modification<F>(
+        &mut self,
+        mut hook: F,
+    ) -> Result<StepOutcome, M::Error>
+    where
+        F: FnMut(T),
+    {
+        let width = self.instruction_width();
+        self.execute(|execution| {
+            let offset = execution.instruction.b.wrapping_sub(&execution.address);
+            if offset >= T::zero() && offset < width {
+                hook(execution.instruction.b.clone());
+            }
+        })
+    }
 
-/// Represent a read- and writable Memory implementation.
-///
-/// Example implementation
-/// ```
-/// # use qelbus::Memory;
-/// struct ByteMemory([i8; 256]);
-///
-/// impl Memory<i8> for ByteMemory {
-///   type Error = std::convert::Infallible;
-///
-///   fn get(&self, index: &i8) -> Result<&i8, Self::Error> {
-///     Ok(&self.0[*index as u8 as usize])
-///   }
-///
-///   fn set(&mut self, index: &i8, value: i8) -> Result<(), Self::Error> {
-///     self.0[*index as u8 as usize] = value;
-///     Ok(())
-///   }
-/// }
-/// ```
-pub trait Memory<T>
-where
-    T: WrappingAdd + From<i8> + Copy,
-{
-    /// An error while using the memory
-    type Error: std::error::Error;
+    /// The amount of cells an instruction takes up in the current [Mode].
+    fn instruction_width(&self) -> T {
+        match self.mode {
+            Mode::Subleq3 => word::three(),
+            Mode::Subleq2 { .. } => word::two(),
+        }
+    }
 
-    /// Get the value at an address or return an error.
+    /// Compute the result of an instruction and whether it branches
+    /// according to the [OverflowMode] and the [branch threshold](Self::set_branch_threshold).
+    ///
+    /// Returns the wrapped result as an error if the subtraction overflows in [OverflowMode::Error].
+    fn subtract(&self, a_value: &T, b_value: &T) -> Result<(T, bool), (T, bool)> {
+        let a_value = a_value.clone();
+        let b_value = b_value.clone();
+        let subtraction = match self.overflow_mode {
+            OverflowMode::Wrap => Ok(b_value.sub_leq(a_value)),
+            OverflowMode::Saturate => Ok(b_value.saturating_sub_leq(a_value)),
+            OverflowMode::Error => b_value
+                .clone()
+                .checked_sub_leq(a_value.clone())
+                .ok_or_else(|| b_value.sub_leq(a_value)),
+        };
+
+        let threshold = |(result, branch): (T, bool)| match &self.branch_threshold {
+            Some(threshold) => {
+                let branch = result <= *threshold;
+                (result, branch)
+            }
+            None => (result, branch),
+        };
+        subtraction.map(threshold).map_err(threshold)
+    }
+
+    /// Compute what executing the current instruction would do, without changing anything.
+    ///
+    /// Returns `None` if the machine has halted, see [Self::halted],
+    /// or if the instruction pointer lies above the maximum, see [Self::set_max_ip].
+    /// The values of A and B are read with [Memory::get], so memories which map I/O to [Memory::load],
+    /// like [IoMemory](memory::IoMemory), are simulated with the values [Memory::get] returns.
+    ///
+    /// ```
+    /// # use qelbus::{StepEffect, Subleq, memory::LinearMemory};
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[6, 7, 3, 8, 8, -1, 2, 5]);
+    /// assert_eq!(
+    ///     subleq.simulate_step().unwrap(),
+    ///     Some(StepEffect { address: 7, value: 3, next_instruction: 3, branched: false, overflowed: false }),
+    /// );
+    /// assert_eq!(subleq.cycles(), 0);
+    ///
+    /// subleq.run().unwrap();
+    /// assert_eq!(subleq.simulate_step().unwrap(), None);
+    /// ```
     ///
     /// # Errors
-    /// Errors are implementation-specific, see [Self::Error].
-    fn get(&self, index: &T) -> Result<&T, Self::Error>;
+    /// Returns an [Memory::Error] when getting from [Memory] fails.
+    /// The error type is specific to the [Memory] implementation.
+    pub fn simulate_step(&self) -> Result<Option<StepEffect<T>>, M::Error> {
+        if self.halted || self.curr_instruction.is_halt_address() || self.ip_out_of_bounds() {
+            return Ok(None);
+        }
 
-    /// Get the instruction at an address or return an error.
+        let Instruction { a, b, c } = self.fetch()?;
+        let a_value = self.memory.get(&a)?;
+        let b_value = self.memory.get(&b)?;
+
+        let ((value, branched), overflowed) = match self.subtract(a_value, b_value) {
+            Ok(subtraction) => (subtraction, false),
+            Err(wrapped) => (wrapped, true),
+        };
+        let next_instruction = if branched {
+            c
+        } else {
+            self.curr_instruction
+                .wrapping_add(&self.instruction_width())
+        };
+
+        Ok(Some(StepEffect {
+            address: b,
+            value,
+            next_instruction,
+            branched,
+            overflowed,
+        }))
+    }
+
+    /// Check whether the executed instruction with argument `c` halts the program,
+    /// given the instruction pointer `next` after it.
+    fn halts(&self, c: &T, next: &T, branch: bool) -> bool {
+        #[cfg(feature = "std")]
+        if let Some(HaltPredicate(predicate)) = &self.halt_predicate {
+            return predicate(c, next);
+        }
+        let _ = next;
+        branch && c.is_halt_address()
+    }
+
+    /// Compute the addresses of the next `n` instructions which are going to be executed,
+    /// starting with the current one, without changing anything.
     ///
-    /// The provided implementation calls [Self::get].
+    /// The results of the instructions are kept aside, so branches which depend on earlier
+    /// instructions of the preview are predicted correctly.
+    /// The instructions themselves are read from the memory as it is now,
+    /// so the preview is wrong once the program modifies an instruction it is going to execute.
+    /// Fewer than `n` addresses are returned if the program halts before that
+    /// or the instruction pointer goes above the maximum, see [Self::set_max_ip].
+    /// Like [Self::simulate_step], values are read with [Memory::get].
+    ///
+    /// ```
+    /// # use qelbus::{Subleq, memory::LinearMemory};
+    /// // Count cell 10 down from 3 to 0.
+    /// let program = [9, 10, 6, 11, 11, 0, 11, 11, -1, 1, 3, 0];
+    /// let subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&program);
+    /// assert_eq!(subleq.preview(4).unwrap(), [0, 3, 0, 3]);
+    /// assert_eq!(subleq.preview(100).unwrap(), [0, 3, 0, 3, 0, 6]);
+    /// assert_eq!(subleq.cycles(), 0);
+    ///
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&program);
+    /// subleq.set_max_ip(Some(5));
+    /// assert_eq!(subleq.preview(100).unwrap(), [0, 3, 0, 3, 0]);
+    /// ```
     ///
     /// # Errors
-    /// Errors are implementation-specfific, see [Self::Error].
-    fn instruction(&self, index: &T) -> Result<Instruction<'_, T>, Self::Error> {
-        Ok(Instruction {
-            a: self.get(index)?,
-            b: self.get(&index.wrapping_add(&T::from(1i8)))?,
-            c: self.get(&index.wrapping_add(&T::from(2i8)))?,
-        })
+    /// Returns an [Memory::Error] when getting from [Memory] fails.
+    /// The error type is specific to the [Memory] implementation.
+    #[cfg(feature = "std")]
+    pub fn preview(&self, n: usize) -> Result<Vec<T>, M::Error> {
+        let mut written = std::collections::BTreeMap::new();
+        let mut addresses = Vec::new();
+        let mut address = self.curr_instruction.clone();
+        if self.halted {
+            return Ok(addresses);
+        }
+
+        while addresses.len() < n && !address.is_halt_address() && !self.above_max_ip(&address) {
+            let Instruction { a, b, c } = self.fetch_at(&address)?;
+            let a_value = match written.get(&a) {
+                Some(value) => value,
+                None => self.memory.get(&a)?,
+            };
+            let b_value = match written.get(&b) {
+                Some(value) => value,
+                None => self.memory.get(&b)?,
+            };
+            let (result, branch) = self
+                .subtract(a_value, b_value)
+                .unwrap_or_else(|wrapped| wrapped);
+            written.insert(b, result);
+
+            addresses.push(address.clone());
+            let next = if branch {
+                c.clone()
+            } else {
+                address.wrapping_add(&self.instruction_width())
+            };
+            if self.halts(&c, &next, branch) {
+                break;
+            }
+            address = next;
+        }
+        Ok(addresses)
     }
 
-    /// Set the value at an address or return an error.
+    /// Halt programs according to `predicate` instead of when they jump to a halt address,
+    /// for dialects of subleq with other halt conventions.
+    ///
+    /// After every executed instruction the predicate is called with its argument C
+    /// and the new instruction pointer. The program halts if it returns true.
+    /// An instruction pointer which is a halt address, see [Word::is_halt_address],
+    /// still halts the program before executing anything.
+    ///
+    /// ```
+    /// # use qelbus::{Subleq, memory::LinearMemory};
+    /// // A dialect which halts by jumping to address 100.
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[3, 3, 100]);
+    /// subleq.set_halt_predicate(|_, ip| *ip == 100);
+    /// subleq.run().unwrap();
+    /// assert!(subleq.halted());
+    /// assert_eq!(subleq.curr_instruction, 100);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn set_halt_predicate<F>(&mut self, predicate: F)
+    where
+        F: Fn(&T, &T) -> bool + Send + Sync + 'static,
+    {
+        self.halt_predicate = Some(HaltPredicate(std::sync::Arc::new(predicate)));
+    }
+
+    /// Remove the predicate set with [Self::set_halt_predicate],
+    /// so programs halt when they jump to a halt address again.
+    #[cfg(feature = "std")]
+    pub fn clear_halt_predicate(&mut self) {
+        self.halt_predicate = None;
+    }
+
+    /// Execute the current instruction, calling `hook` with what is about to happen
+    /// before the result is stored.
+    fn execute<F>(&mut self, mut hook: F) -> Result<StepOutcome, M::Error>
+    where
+        F: FnMut(&Execution<T>),
+    {
+        if self.halted || self.curr_instruction.is_halt_address() {
+            self.halted = true;
+            return Ok(StepOutcome::Halted);
+        }
+        if self.ip_out_of_bounds() {
+            if let Some(error) = self
+                .memory
+                .instruction_pointer_error(&self.curr_instruction)
+            {
+                return Err(error);
+            }
+            return Ok(StepOutcome::IpOutOfBounds);
+        }
+
+        let previous_instruction = self.curr_instruction.clone();
+        let Instruction { a, b, c } = self.fetch()?;
+
+        let a_value = self.memory.load(&a)?;
+        let b_value = self.memory.load(&b)?;
+
+        let (result, branch) = match self.subtract(&a_value, &b_value) {
+            Ok(subtraction) => subtraction,
+            Err(wrapped) => match self.memory.overflow_error(
+                &previous_instruction,
+                a_value.clone(),
+                b_value.clone(),
+            ) {
+                Some(error) => return Err(error),
+                None => wrapped,
+            },
+        };
+        let execution = Execution {
+            address: previous_instruction,
+            instruction: Instruction { a, b, c },
+            a_value,
+            b_value,
+            result,
+            branch,
+        };
+        hook(&execution);
+        let Instruction { b, c, .. } = execution.instruction;
+
+        // Store the result first, so a failing write leaves the instruction pointer untouched.
+        self.memory.set(&b, execution.result)?;
+
+        if branch {
+            self.branches_taken += 1;
+            self.curr_instruction = c.clone();
+        } else {
+            self.branches_not_taken += 1;
+            self.curr_instruction = self
+                .curr_instruction
+                .wrapping_add(&self.instruction_width());
+        }
+        let outcome = if self.halts(&c, &self.curr_instruction, branch) {
+            self.halted = true;
+            StepOutcome::Halted
+        } else {
+            StepOutcome::Continued
+        };
+        self.cycles += 1;
+
+        #[cfg(feature = "std")]
+        if self.profiling {
+            *self.profile.entry(execution.address.clone()).or_insert(0) += 1;
+        }
+
+        #[cfg(feature = "std")]
+        if self.covering {
+            self.coverage.insert(execution.address.clone());
+        }
+
+        #[cfg(feature = "std")]
+        if self.history_capacity > 0 {
+            if self.history.len() == self.history_capacity {
+                self.history.pop_front();
+            }
+            self.history.push_back(Change {
+                address: b,
+                old_value: execution.b_value,
+                previous_instruction: execution.address,
+                branched: branch,
+            });
+        }
+
+        Ok(outcome)
+    }
+
+    /// Execute the current instruction like [Self::step] and return the cell it wrote to.
+    ///
+    /// Returns `None` without executing anything if the machine has already halted, see [Self::halted].
+    ///
+    /// ```
+    /// # use qelbus::{Subleq, Write, memory::LinearMemory};
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[6, 7, 3, 8, 8, -1, 2, 5]);
+    /// assert_eq!(
+    ///     subleq.step_observed().unwrap(),
+    ///     Some(Write { address: 7, old: 5, new: 3, branched: false })
+    /// );
+    /// assert_eq!(
+    ///     subleq.step_observed().unwrap(),
+    ///     Some(Write { address: 8, old: 0, new: 0, branched: true })
+    /// );
+    /// assert_eq!(subleq.step_observed().unwrap(), None);
+    /// ```
     ///
     /// # Errors
-    /// Errors are implementation-specific, see [Self::Error].
+    /// Returns an [Memory::Error] when getting or setting [Memory] fails.
+    /// The error type is specific to the [Memory] implementation.
+    pub fn step_observed(&mut self) -> Result<Option<Write<T>>, M::Error> {
+        let mut write = None;
+        self.execute(|execution| {
+            write = Some(Write {
+                address: execution.instruction.b.clone(),
+                old: execution.b_value.clone(),
+                new: execution.result.clone(),
+                branched: execution.branch,
+            });
+        })?;
+        Ok(write)
+    }
+
+    /// Set the maximum amount of executed instructions which can be undone with [Self::step_back].
+    ///
+    /// The capacity is 0 by default, which disables recording the history.
+    /// Lowering the capacity discards the oldest recorded instructions.
+    #[cfg(feature = "std")]
+    pub fn set_history_capacity(&mut self, capacity: usize) {
+        self.history_capacity = capacity;
+        while self.history.len() > capacity {
+            self.history.pop_front();
+        }
+    }
+
+    /// Undo the most recently executed instruction recorded in the history.
+    ///
+    /// This restores the cell it wrote to and the instruction pointer.
+    /// Nothing happens if the history is empty, see [Self::set_history_capacity].
+    ///
+    /// ```
+    /// # use qelbus::{Memory, Subleq, memory::LinearMemory};
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[6, 7, 3, 0, 0, -1, 2, 5]);
+    /// subleq.set_history_capacity(100);
+    ///
+    /// subleq.step().unwrap();
+    /// assert_eq!(subleq.memory.get(&7), Ok(&3));
+    ///
+    /// subleq.step_back().unwrap();
+    /// assert_eq!(subleq.memory.get(&7), Ok(&5));
+    /// assert_eq!(subleq.curr_instruction, 0);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an [Memory::Error] when setting [Memory] fails.
+    /// The error type is specific to the [Memory] implementation.
+    #[cfg(feature = "std")]
+    pub fn step_back(&mut self) -> Result<(), M::Error> {
+        if let Some(change) = self.history.back().cloned() {
+            self.memory.set(&change.address, change.old_value)?;
+            self.curr_instruction = change.previous_instruction;
+            self.halted = false;
+            self.cycles = self.cycles.saturating_sub(1);
+            if change.branched {
+                self.branches_taken = self.branches_taken.saturating_sub(1);
+            } else {
+                self.branches_not_taken = self.branches_not_taken.saturating_sub(1);
+            }
+            self.history.pop_back();
+        }
+        Ok(())
+    }
+
+    /// Whether the machine has halted.
+    ///
+    /// This becomes true when an instruction jumps to a halt address, see [Word::is_halt_address],
+    /// or when stepping while the instruction pointer already is a halt address.
+    /// Afterwards [Self::step] does nothing.
+    ///
+    /// ```
+    /// # use qelbus::{Memory, StepOutcome, Subleq, memory::LinearMemory};
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[3, 3, -1]);
+    /// assert!(!subleq.halted());
+    ///
+    /// subleq.run().unwrap();
+    /// assert!(subleq.halted());
+    /// assert_eq!(subleq.cycles(), 1);
+    ///
+    /// assert_eq!(subleq.step().unwrap(), StepOutcome::Halted);
+    /// assert_eq!(subleq.cycles(), 1);
+    /// ```
+    pub fn halted(&self) -> bool {
+        self.halted
+    }
+
+    /// Enable or disable counting how often the instruction at each address is executed, see [Self::profile].
+    ///
+    /// Profiling is disabled by default. Disabling it keeps the counts collected so far.
+    #[cfg(feature = "std")]
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profiling = enabled;
+    }
+
+    /// Get how often the instruction at each address has been executed while profiling was enabled.
+    ///
+    /// Addresses whose instruction has never been executed are missing.
+    ///
+    /// ```
+    /// # use qelbus::{Subleq, memory::LinearMemory};
+    /// // Subtracts 1 from X until it is no longer positive.
+    /// let program = [9, 10, 6, 11, 11, 0, 11, 11, -1, 1, 3, 0];
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&program);
+    /// subleq.set_profiling(true);
+    /// subleq.run().unwrap();
+    ///
+    /// assert_eq!(subleq.profile()[&0], 3);
+    /// assert_eq!(subleq.profile()[&3], 2);
+    /// assert_eq!(subleq.profile()[&6], 1);
+    ///
+    /// subleq.clear_profile();
+    /// assert!(subleq.profile().is_empty());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn profile(&self) -> &std::collections::BTreeMap<T, u64> {
+        &self.profile
+    }
+
+    /// Forget the counts collected for [Self::profile].
+    #[cfg(feature = "std")]
+    pub fn clear_profile(&mut self) {
+        self.profile.clear();
+    }
+
+    /// Enable or disable recording which instructions are executed, see [Self::coverage].
+    ///
+    /// Coverage is disabled by default. Disabling it keeps the addresses recorded so far.
+    #[cfg(feature = "std")]
+    pub fn set_coverage(&mut self, enabled: bool) {
+        self.covering = enabled;
+    }
+
+    /// Get the addresses of the instructions which have been executed while coverage was enabled.
+    ///
+    /// Unlike the [profile](Self::profile) this doesn't count the executions,
+    /// which makes it a cheap measure of how much of a program a fuzzer has explored.
+    ///
+    /// ```
+    /// # use qelbus::{Subleq, memory::LinearMemory};
+    /// // The instruction at 3 is never executed.
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 12>>::with_program(&[9, 9, 6, 9, 9, 6, 9, 9, -1]);
+    /// subleq.set_coverage(true);
+    /// subleq.run().unwrap();
+    ///
+    /// assert_eq!(subleq.coverage().iter().collect::<Vec<_>>(), [&0, &6]);
+    /// assert_eq!(subleq.coverage_ratio(), Some(0.5));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn coverage(&self) -> &std::collections::BTreeSet<T> {
+        &self.coverage
+    }
+
+    /// Get the fraction of the cells of the memory which belong to an executed instruction,
+    /// see [Self::coverage].
+    ///
+    /// Returns `None` if the memory doesn't know its size, see [Memory::size_hint].
+    #[cfg(feature = "std")]
+    pub fn coverage_ratio(&self) -> Option<f64> {
+        let size = self.memory.size_hint()?;
+        if size == 0 {
+            return Some(0.0);
+        }
+
+        let width = self.instruction_width();
+        let mut cells = std::collections::BTreeSet::new();
+        for address in &self.coverage {
+            let mut cell = address.clone();
+            while cell.wrapping_sub(address) < width {
+                cells.insert(cell.clone());
+                cell = cell.wrapping_add(&T::one());
+            }
+        }
+        Some(cells.len() as f64 / size as f64)
+    }
+
+    /// Forget the addresses recorded for [Self::coverage].
+    #[cfg(feature = "std")]
+    pub fn clear_coverage(&mut self) {
+        self.coverage.clear();
+    }
+
+    /// Get the amount of instructions which have been successfully executed
+    /// since construction or the last call to [Self::reset_cycles].
+    ///
+    /// ```
+    /// # use qelbus::{Subleq, Memory};
+    /// # struct ByteMemory([i8; 256]);
+    /// #
+    /// # impl Memory<i8> for ByteMemory {
+    /// #   type Error = std::convert::Infallible;
+    /// #
+    /// #   fn get(&self, index: &i8) -> Result<&i8, Self::Error> {
+    /// #     Ok(&self.0[*index as u8 as usize])
+    /// #   }
+    /// #   fn set(&mut self, index: &i8, value: i8) -> Result<(), Self::Error> {
+    /// #     self.0[*index as u8 as usize] = value;
+    /// #     Ok(())
+    /// #   }
+    /// # }
+    /// # impl ByteMemory {
+    /// #   fn new() -> Self { Self([0; 256]) }
+    /// # }
+    /// let mut memory = ByteMemory::new();
+    /// memory.0[..6].copy_from_slice(&[6, 6, 3, 6, 6, -1]);
+    ///
+    /// let mut subleq = Subleq::new(memory);
+    /// subleq.run().unwrap();
+    /// assert_eq!(subleq.cycles(), 2);
+    ///
+    /// subleq.reset_cycles();
+    /// assert_eq!(subleq.cycles(), 0);
+    /// ```
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Reset the amount of executed instructions returned by [Self::cycles] to 0.
+    pub fn reset_cycles(&mut self) {
+        self.cycles = 0;
+    }
+
+    /// Get the amount of executed instructions which branched to their argument C.
+    ///
+    /// Together with [Self::branches_not_taken] and the [profile](Self::profile)
+    /// this shows how the loops of a program behave.
+    ///
+    /// ```
+    /// # use qelbus::{Subleq, memory::LinearMemory};
+    /// // A loop which counts down from 3.
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[9, 10, 6, 11, 11, 0, 11, 11, -1, 1, 3, 0]);
+    /// subleq.run().unwrap();
+    /// assert_eq!(subleq.branches_taken(), 4);
+    /// assert_eq!(subleq.branches_not_taken(), 2);
+    ///
+    /// subleq.reset_branch_stats();
+    /// assert_eq!(subleq.branches_taken(), 0);
+    /// ```
+    pub fn branches_taken(&self) -> u64 {
+        self.branches_taken
+    }
+
+    /// Get the amount of executed instructions which continued with the next instruction,
+    /// see [Self::branches_taken].
+    pub fn branches_not_taken(&self) -> u64 {
+        self.branches_not_taken
+    }
+
+    /// Reset the amounts returned by [Self::branches_taken] and [Self::branches_not_taken] to 0.
+    pub fn reset_branch_stats(&mut self) {
+        self.branches_taken = 0;
+        self.branches_not_taken = 0;
+    }
+
+    /// Execute instructions until the program halts.
+    ///
+    /// A program halts by jumping to a halt address, see [Word::is_halt_address].
+    /// For signed words this is any negative address,
+    /// which conventionally is done with an instruction like `Z Z -1`.
+    ///
+    /// ```
+    /// # use qelbus::{RunReport, RunResult, Subleq, Memory};
+    /// # struct ByteMemory([i8; 256]);
+    /// #
+    /// # impl Memory<i8> for ByteMemory {
+    /// #   type Error = std::convert::Infallible;
+    /// #
+    /// #   fn get(&self, index: &i8) -> Result<&i8, Self::Error> {
+    /// #     Ok(&self.0[*index as u8 as usize])
+    /// #   }
+    /// #   fn set(&mut self, index: &i8, value: i8) -> Result<(), Self::Error> {
+    /// #     self.0[*index as u8 as usize] = value;
+    /// #     Ok(())
+    /// #   }
+    /// # }
+    /// # impl ByteMemory {
+    /// #   fn new() -> Self { Self([0; 256]) }
+    /// # }
+    /// let mut memory = ByteMemory::new();
+    /// memory.0[..3].copy_from_slice(&[3, 3, -1]);
+    ///
+    /// let mut subleq = Subleq::new(memory);
+    /// let report = subleq.run().unwrap();
+    /// assert_eq!(report, RunReport { steps: 1, reason: RunResult::Halted });
+    /// assert_eq!(subleq.curr_instruction, -1);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an [Memory::Error] when getting or setting [Memory] fails.
+    /// The error type is specific to the [Memory] implementation.
+    #[must_use = "the report tells how many instructions were executed"]
+    pub fn run(&mut self) -> Result<RunReport, M::Error> {
+        let mut steps = 0;
+        let reason = loop {
+            match self.step_counted(&mut steps)? {
+                StepOutcome::Continued => {}
+                StepOutcome::Halted => {
+                    if !self.resume_after_halt() {
+                        break RunResult::Halted;
+                    }
+                }
+                StepOutcome::IpOutOfBounds => break RunResult::IpOutOfBounds,
+            }
+        };
+        Ok(RunReport { steps, reason })
+    }
+
+    /// Execute instructions until the program halts or `max_steps` instructions have been executed.
+    ///
+    /// This guarantees termination for programs which might loop forever.
+    ///
+    /// ```
+    /// # use qelbus::{RunResult, Subleq, memory::LinearMemory};
+    /// // Z Z 0 loops forever.
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[3, 3, 0]);
+    /// assert_eq!(subleq.run_capped(1000).unwrap(), RunResult::LimitReached);
+    /// assert_eq!(subleq.cycles(), 1000);
+    ///
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[3, 3, -1]);
+    /// assert_eq!(subleq.run_capped(1000).unwrap(), RunResult::Halted);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an [Memory::Error] when getting or setting [Memory] fails.
+    /// The error type is specific to the [Memory] implementation.
+    pub fn run_capped(&mut self, max_steps: u64) -> Result<RunResult, M::Error> {
+        for _ in 0..max_steps {
+            match self.step()? {
+                StepOutcome::Continued => {}
+                StepOutcome::Halted => {
+                    if !self.resume_after_halt() {
+                        return Ok(RunResult::Halted);
+                    }
+                }
+                StepOutcome::IpOutOfBounds => return Ok(RunResult::IpOutOfBounds),
+            }
+        }
+
+        if self.halted || self.curr_instruction.is_halt_address() {
+            Ok(RunResult::Halted)
+        } else {
+            Ok(RunResult::LimitReached)
+        }
+    }
+
+    /// Call the halt handler after the program halted, see [Self::set_halt_handler].
+    ///
+    /// Returns whether execution resumes, in which case the machine is no longer halted.
+    #[cfg(feature = "std")]
+    fn resume_after_halt(&mut self) -> bool {
+        // The handler is taken out while it runs, so a handler which runs the machine itself just stops.
+        let Some(handler) = self.halt_handler.take() else {
+            return false;
+        };
+        let action = match handler.0.lock() {
+            Ok(mut handler) => handler(self),
+            Err(_) => HaltAction::Stop,
+        };
+        self.halt_handler.get_or_insert(handler);
+
+        let resume = action == HaltAction::Resume;
+        if resume {
+            self.halted = false;
+        }
+        resume
+    }
+
+    /// Without the `std` feature there is no halt handler, so execution never resumes.
+    #[cfg(not(feature = "std"))]
+    fn resume_after_halt(&mut self) -> bool {
+        false
+    }
+
+    /// Call `handler` whenever the program halts during [Self::run], [Self::run_capped] or [Self::step_n],
+    /// which decides whether execution stops or resumes.
+    ///
+    /// This allows implementing system calls: a program jumps to a halt address,
+    /// the handler inspects and changes the machine, for example by setting the instruction pointer
+    /// to a return address, and resumes execution.
+    /// If the instruction pointer is still a halt address when resuming, the program halts again right away
+    /// and the handler is called again.
+    ///
+    /// ```
+    /// # use qelbus::{HaltAction, Memory, Subleq, memory::LinearMemory};
+    /// // Call the system, which stores 5 at 12 and returns to the address at 9,
+    /// // subtract the value at 12 from 13, halt.
+    /// let program = [11, 11, -1, 12, 13, 6, 11, 11, -1, 3, 0, 0, 0, 0];
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&program);
+    ///
+    /// let mut calls = 0;
+    /// subleq.set_halt_handler(move |subleq| {
+    ///     calls += 1;
+    ///     if calls > 1 {
+    ///         return HaltAction::Stop;
+    ///     }
+    ///     subleq.memory.set(&12, 5).unwrap();
+    ///     subleq.curr_instruction = *subleq.memory.get(&9).unwrap();
+    ///     HaltAction::Resume
+    /// });
+    ///
+    /// assert_eq!(subleq.run().unwrap().steps, 3);
+    /// assert_eq!(subleq.memory.get(&13), Ok(&-5));
+    /// assert!(subleq.halted());
+    ///
+    /// // Run Z Z -1 twice by jumping back to the start once.
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[3, 3, -1]);
+    /// let mut resumed = false;
+    /// subleq.set_halt_handler(move |subleq| {
+    ///     if resumed {
+    ///         return HaltAction::Stop;
+    ///     }
+    ///     resumed = true;
+    ///     subleq.curr_instruction = 0;
+    ///     HaltAction::Resume
+    /// });
+    /// assert_eq!(subleq.step_n(10).unwrap(), 2);
+    ///
+    /// // The handler may reset the counters, the report still counts the executed instructions.
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[6, 6, 3, 6, 6, -1]);
+    /// subleq.step().unwrap();
+    /// subleq.set_halt_handler(|subleq| {
+    ///     subleq.reset_cycles();
+    ///     HaltAction::Stop
+    /// });
+    /// assert_eq!(subleq.run().unwrap().steps, 1);
+    /// assert_eq!(subleq.cycles(), 0);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn set_halt_handler<F>(&mut self, handler: F)
+    where
+        F: FnMut(&mut Self) -> HaltAction + Send + 'static,
+    {
+        let handler: Box<HaltHandlerFn<T, M>> = Box::new(handler);
+        self.halt_handler = Some(HaltHandler(std::sync::Arc::new(std::sync::Mutex::new(
+            handler,
+        ))));
+    }
+
+    /// Remove the handler set with [Self::set_halt_handler], so halting stops execution again.
+    #[cfg(feature = "std")]
+    pub fn clear_halt_handler(&mut self) {
+        self.halt_handler = None;
+    }
+
+    /// Execute instructions until `predicate` holds for the machine after an instruction,
+    /// the program halts or `max` instructions have been executed.
+    ///
+    /// Returns whether the predicate holds. It is checked after every instruction,
+    /// so at least one instruction is executed unless the program has already halted.
+    ///
+    /// ```
+    /// # use qelbus::{Memory, Subleq, memory::LinearMemory};
+    /// // Count cell 10 down from 3 to 0.
+    /// let program = [9, 10, 6, 11, 11, 0, 11, 11, -1, 1, 3, 0];
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&program);
+    /// assert!(subleq.step_until(|subleq| subleq.memory.get(&10) == Ok(&1), 100).unwrap());
+    /// assert_eq!(subleq.cycles(), 3);
+    ///
+    /// assert!(!subleq.step_until(|subleq| subleq.memory.get(&10) == Ok(&-1), 100).unwrap());
+    /// assert!(subleq.halted());
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an [Memory::Error] when getting or setting [Memory] fails.
+    /// The error type is specific to the [Memory] implementation.
+    pub fn step_until<F>(&mut self, mut predicate: F, max: u64) -> Result<bool, M::Error>
+    where
+        F: FnMut(&Self) -> bool,
+    {
+        for _ in 0..max {
+            let outcome = self.step()?;
+            if predicate(self) {
+                return Ok(true);
+            }
+            if outcome != StepOutcome::Continued {
+                break;
+            }
+        }
+        Ok(false)
+    }
+
+    /// Execute up to `max` instructions like [Self::run_capped], writing a trace of every executed instruction.
+    ///
+    /// Every instruction is written as one line of tab separated fields:
+    /// the instruction pointer, the arguments A, B and C,
+    /// the values read from A and B, the result written to B
+    /// and the [kind](InstrKind) of the instruction.
+    ///
+    /// ```
+    /// # use qelbus::{Subleq, memory::LinearMemory};
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[6, 7, 3, 8, 8, -1, 2, 5]);
+    ///
+    /// let mut trace = Vec::new();
+    /// subleq.run_tracing(&mut trace, 100).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(trace).unwrap(),
+    ///     "0\t6\t7\t3\t2\t5\t3\tsub\n3\t8\t8\t-1\t0\t0\t0\thalt\n",
+    /// );
+    /// ```
+    ///
+    /// An instruction which fails, for example because B is read-only, isn't written.
+    ///
+    /// ```
+    /// # use qelbus::{Subleq, TraceError, memory::{LinearMemory, Protected}};
+    /// let memory = LinearMemory::<i32, 4>::from_slice(&[3, 3, -1, 7]).unwrap();
+    /// let mut subleq = Subleq::new(Protected::new(memory, 3..4));
+    ///
+    /// let mut trace = Vec::new();
+    /// assert!(matches!(subleq.run_tracing(&mut trace, 100), Err(TraceError::Memory(_))));
+    /// assert!(trace.is_empty());
+    /// ```
+    ///
+    /// # Errors
+    /// Returns [TraceError::Memory] when getting or setting [Memory] fails
+    /// and [TraceError::Io] when writing to `out` fails.
+    #[cfg(feature = "std")]
+    pub fn run_tracing<W>(&mut self, out: &mut W, max: u64) -> Result<(), TraceError<M::Error>>
+    where
+        T: core::fmt::Display,
+        W: std::io::Write,
+    {
+        for _ in 0..max {
+            // The hook runs before the result is written, so the line is only written once the step succeeded.
+            let mut executed = None;
+            let outcome = self
+                .execute(|execution| executed = Some(execution.clone()))
+                .map_err(TraceError::Memory)?;
+            if let Some(Execution {
+                address,
+                instruction: Instruction { a, b, c },
+                a_value,
+                b_value,
+                result,
+                ..
+            }) = executed
+            {
+                let kind = classify_instruction(&address, &a, &b, &c);
+                writeln!(
+                    out,
+                    "{address}\t{a}\t{b}\t{c}\t{a_value}\t{b_value}\t{result}\t{kind}"
+                )?;
+            }
+
+            if outcome != StepOutcome::Continued {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Execute up to `count` instructions and return how many were executed.
+    ///
+    /// Fewer than `count` instructions are executed if the program halts before that
+    /// and the [halt handler](Self::set_halt_handler) doesn't resume it,
+    /// see [Self::run] for when a program halts.
+    /// If the instruction pointer goes above the maximum, see [Self::set_max_ip],
+    /// the next instruction is handled like by [Self::step]:
+    /// the error of the memory is returned, or execution stops at [StepOutcome::IpOutOfBounds].
+    ///
+    /// ```
+    /// # use qelbus::{Subleq, Memory};
+    /// # struct ByteMemory([i8; 256]);
+    /// #
+    /// # impl Memory<i8> for ByteMemory {
+    /// #   type Error = std::convert::Infallible;
+    /// #
+    /// #   fn get(&self, index: &i8) -> Result<&i8, Self::Error> {
+    /// #     Ok(&self.0[*index as u8 as usize])
+    /// #   }
+    /// #   fn set(&mut self, index: &i8, value: i8) -> Result<(), Self::Error> {
+    /// #     self.0[*index as u8 as usize] = value;
+    /// #     Ok(())
+    /// #   }
+    /// # }
+    /// # impl ByteMemory {
+    /// #   fn new() -> Self { Self([0; 256]) }
+    /// # }
+    /// let mut memory = ByteMemory::new();
+    /// memory.0[..6].copy_from_slice(&[6, 6, 3, 6, 6, -1]);
+    ///
+    /// let mut subleq = Subleq::new(memory);
+    /// assert_eq!(subleq.step_n(1).unwrap(), 1);
+    /// assert_eq!(subleq.step_n(1000).unwrap(), 1);
+    /// ```
+    ///
+    /// ```
+    /// # use qelbus::{Subleq, memory::{Error, LinearMemory}};
+    /// // Z Z 9 jumps past the maximum.
+    /// let memory = LinearMemory::<i32, 16>::from_slice(&[3, 3, 9]).unwrap();
+    /// let mut subleq = Subleq::new(memory);
+    /// subleq.set_max_ip(Some(6));
+    /// assert_eq!(subleq.step_n(10), Err(Error::InstructionPointerOutOfBounds(9)));
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an [Memory::Error] when getting or setting [Memory] fails.
+    /// The error type is specific to the [Memory] implementation.
+    pub fn step_n(&mut self, count: usize) -> Result<usize, M::Error> {
+        let mut executed = 0;
+        while executed < count as u64 {
+            match self.step_counted(&mut executed)? {
+                StepOutcome::Continued => {}
+                StepOutcome::Halted => {
+                    if !self.resume_after_halt() {
+                        break;
+                    }
+                }
+                StepOutcome::IpOutOfBounds => break,
+            }
+        }
+        Ok(executed as usize)
+    }
+
+    /// Execute instructions until the program halts, yielding to the tokio runtime after every `chunk` instructions.
+    ///
+    /// This keeps an executor responsive while it runs many machines on the same thread.
+    /// A `chunk` of 0 is treated as 1.
+    /// Like [Self::run], the [halt handler](Self::set_halt_handler) decides whether a halted program resumes.
+    ///
+    /// ```
+    /// # use qelbus::{RunReport, RunResult, Subleq, memory::LinearMemory};
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[9, 10, 6, 11, 11, 0, 11, 11, -1, 1, 3, 0]);
+    ///
+    /// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    /// let report = runtime.block_on(subleq.run_async(2)).unwrap();
+    /// assert_eq!(report, RunReport { steps: 6, reason: RunResult::Halted });
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an [Memory::Error] when getting or setting [Memory] fails.
+    /// The error type is specific to the [Memory] implementation.
+    #[cfg(feature = "tokio")]
+    #[must_use = "the report tells how many instructions were executed"]
+    pub async fn run_async(&mut self, chunk: usize) -> Result<RunReport, M::Error> {
+        let chunk = chunk.max(1);
+        let mut steps = 0;
+        let reason = 'run: loop {
+            for _ in 0..chunk {
+                match self.step_counted(&mut steps)? {
+                    StepOutcome::Continued => {}
+                    StepOutcome::Halted => {
+                        if !self.resume_after_halt() {
+                            break 'run RunResult::Halted;
+                        }
+                    }
+                    StepOutcome::IpOutOfBounds => break 'run RunResult::IpOutOfBounds,
+                }
+            }
+            tokio::task::yield_now().await;
+        };
+        Ok(RunReport { steps, reason })
+    }
+
+    /// Iterate over the executed instructions, executing one instruction per iteration.
+    ///
+    /// Every item is the address of the executed instruction together with its arguments A, B and C.
+    /// The iterator ends when the program halts or after an error was returned.
+    ///
+    /// ```
+    /// # use qelbus::{Subleq, memory::LinearMemory};
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[6, 6, 3, 6, 6, -1]);
+    /// let trace: Result<Vec<_>, _> = subleq.iter().collect();
+    /// assert_eq!(trace.unwrap(), [(0, (6, 6, 3)), (3, (6, 6, -1))]);
+    /// ```
+    pub fn iter(&mut self) -> StepIter<'_, T, M> {
+        StepIter {
+            subleq: self,
+            failed: false,
+            remaining: None,
+        }
+    }
+
+    /// Iterate over the executed instructions like [Self::iter],
+    /// but end after `max_steps` instructions even if the program hasn't halted.
+    ///
+    /// This makes iterating over programs which might never halt safe.
+    ///
+    /// ```
+    /// # use qelbus::{Subleq, memory::LinearMemory};
+    /// // Loops forever.
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[3, 3, 0]);
+    /// assert_eq!(subleq.iter_capped(1_000).count(), 1_000);
+    /// assert!(!subleq.halted());
+    /// ```
+    pub fn iter_capped(&mut self, max_steps: u64) -> StepIter<'_, T, M> {
+        StepIter {
+            subleq: self,
+            failed: false,
+            remaining: Some(max_steps),
+        }
+    }
+
+    /// Add a breakpoint at an address, which makes [Self::run_to_breakpoint] stop
+    /// when the instruction at that address is about to be executed.
+    #[cfg(feature = "std")]
+    pub fn add_breakpoint(&mut self, addr: T) {
+        if !self.breakpoints.contains(&addr) {
+            self.breakpoints.push(addr);
+        }
+    }
+
+    /// Remove the breakpoint at an address, if there is one.
+    #[cfg(feature = "std")]
+    pub fn remove_breakpoint(&mut self, addr: T) {
+        self.breakpoints.retain(|breakpoint| *breakpoint != addr);
+    }
+
+    /// Add a watchpoint at an address, which makes [Self::run_to_breakpoint] stop
+    /// after an instruction has written to that address.
+    #[cfg(feature = "std")]
+    pub fn add_watchpoint(&mut self, addr: T) {
+        if !self.watchpoints.contains(&addr) {
+            self.watchpoints.push(addr);
+        }
+    }
+
+    /// Remove the watchpoint at an address, if there is one.
+    #[cfg(feature = "std")]
+    pub fn remove_watchpoint(&mut self, addr: T) {
+        self.watchpoints.retain(|watchpoint| *watchpoint != addr);
+    }
+
+    /// Execute instructions until a breakpoint is reached, a watched address is written or the program halts.
+    ///
+    /// At least one instruction is executed, so calling this again after
+    /// reaching a breakpoint continues execution.
+    /// See [Self::run] for when a program halts.
+    ///
+    /// ```
+    /// # use qelbus::{BreakReason, Subleq, memory::LinearMemory};
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[9, 9, 3, 9, 9, 6, 9, 10, -1]);
+    /// subleq.add_breakpoint(3);
+    /// subleq.add_breakpoint(6);
+    /// subleq.add_watchpoint(10);
+    /// assert_eq!(subleq.run_to_breakpoint().unwrap(), BreakReason::Breakpoint(3));
+    ///
+    /// subleq.remove_breakpoint(6);
+    /// assert_eq!(subleq.run_to_breakpoint().unwrap(), BreakReason::Watchpoint(10));
+    /// assert_eq!(subleq.run_to_breakpoint().unwrap(), BreakReason::Halted);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an [Memory::Error] when getting or setting [Memory] fails.
+    /// The error type is specific to the [Memory] implementation.
+    #[cfg(feature = "std")]
+    pub fn run_to_breakpoint(&mut self) -> Result<BreakReason<T>, M::Error> {
+        loop {
+            let mut written = None;
+            let outcome =
+                self.execute(|execution| written = Some(execution.instruction.b.clone()))?;
+
+            if let Some(address) = written.filter(|address| self.watchpoints.contains(address)) {
+                return Ok(BreakReason::Watchpoint(address));
+            }
+            match outcome {
+                StepOutcome::Continued => {}
+                StepOutcome::Halted => return Ok(BreakReason::Halted),
+                StepOutcome::IpOutOfBounds => return Ok(BreakReason::IpOutOfBounds),
+            }
+            if self.breakpoints.contains(&self.curr_instruction) {
+                return Ok(BreakReason::Breakpoint(self.curr_instruction.clone()));
+            }
+        }
+    }
+
+    /// Compare the state of this machine to the state of `other`.
+    ///
+    /// The cells are compared with [Memory::iter_cells], a cell missing from one of the memories counts as 0.
+    /// Memories which don't implement [Memory::iter_cells] only differ in the instruction pointer.
+    ///
+    /// ```
+    /// # use qelbus::{Diff, Subleq, memory::LinearMemory};
+    /// let program = [6, 7, 3, 8, 8, -1, 2, 5];
+    /// let mut expected = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&program);
+    /// let actual = expected.clone();
+    /// expected.run().unwrap();
+    ///
+    /// let diff = expected.diff(&actual);
+    /// assert_eq!(diff, Diff { cells: vec![(7, 3, 5)], curr_instruction: Some((-1, 0)) });
+    /// assert!(expected.diff(&expected).is_empty());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn diff(&self, other: &Self) -> Diff<T> {
+        let mut cells = std::collections::BTreeMap::new();
+        for (address, value) in self.memory.iter_cells() {
+            cells.insert(address, (value, T::zero()));
+        }
+        for (address, value) in other.memory.iter_cells() {
+            cells.entry(address).or_insert((T::zero(), T::zero())).1 = value;
+        }
+
+        Diff {
+            cells: cells
+                .into_iter()
+                .filter(|(_, (left, right))| left != right)
+                .map(|(address, (left, right))| (address, left, right))
+                .collect(),
+            curr_instruction: (self.curr_instruction != other.curr_instruction).then(|| {
+                (
+                    self.curr_instruction.clone(),
+                    other.curr_instruction.clone(),
+                )
+            }),
+        }
+    }
+}
+
+impl<T, M> Subleq<T, M>
+where
+    T: Word,
+    M: Memory<T> + Clone,
+{
+    /// Create an independent copy of the machine, including its memory, breakpoints, watchpoints and history.
+    ///
+    /// The copies share no state, so after forking each of them can be driven on its own,
+    /// for example to explore different inputs on separate threads.
+    ///
+    /// ```
+    /// # use qelbus::{Memory, Subleq, memory::LinearMemory};
+    /// let subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[6, 7, 3, 8, 8, -1, 2, 5]);
+    ///
+    /// let handles: Vec<_> = [1, 2]
+    ///     .into_iter()
+    ///     .map(|input| {
+    ///         let mut fork = subleq.fork();
+    ///         std::thread::spawn(move || {
+    ///             fork.memory.set(&6, input).unwrap();
+    ///             fork.run().unwrap();
+    ///             *fork.memory.get(&7).unwrap()
+    ///         })
+    ///     })
+    ///     .collect();
+    ///
+    /// let results: Vec<_> = handles.into_iter().map(|handle| handle.join().unwrap()).collect();
+    /// assert_eq!(results, [4, 3]);
+    /// assert_eq!(subleq.memory.get(&7), Ok(&5));
+    /// ```
+    pub fn fork(&self) -> Self {
+        self.clone()
+    }
+
+    /// Save the current state of the machine, which can be restored with [Self::restore].
+    ///
+    /// ```
+    /// # use qelbus::{Memory, Subleq, memory::LinearMemory};
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[6, 7, 3, 8, 8, -1, 2, 5]);
+    /// let snapshot = subleq.snapshot();
+    ///
+    /// subleq.run().unwrap();
+    /// assert_eq!(subleq.memory.get(&7), Ok(&3));
+    ///
+    /// subleq.restore(snapshot);
+    /// assert_eq!(subleq.memory.get(&7), Ok(&5));
+    /// assert_eq!(subleq.curr_instruction, 0);
+    /// ```
+    pub fn snapshot(&self) -> Snapshot<T, M> {
+        Snapshot {
+            memory: self.memory.clone(),
+            curr_instruction: self.curr_instruction.clone(),
+            cycles: self.cycles,
+            branches_taken: self.branches_taken,
+            branches_not_taken: self.branches_not_taken,
+            halted: self.halted,
+        }
+    }
+
+    /// Restore a state saved with [Self::snapshot].
+    ///
+    /// The history used by [Self::step_back] is cleared, breakpoints and watchpoints are kept.
+    pub fn restore(&mut self, snapshot: Snapshot<T, M>) {
+        self.memory = snapshot.memory;
+        self.curr_instruction = snapshot.curr_instruction;
+        self.cycles = snapshot.cycles;
+        self.branches_taken = snapshot.branches_taken;
+        self.branches_not_taken = snapshot.branches_not_taken;
+        self.halted = snapshot.halted;
+        #[cfg(feature = "std")]
+        self.history.clear();
+    }
+
+    /// Save a copy of the current memory, which [Self::reset] restores from then on.
+    ///
+    /// The copy is stored on the heap, so the machine itself stays the same size.
+    #[cfg(feature = "std")]
+    pub fn save_initial_memory(&mut self) {
+        self.initial_memory = Some(Box::new(self.memory.clone()));
+    }
+
+    /// Reset the machine to run its program again from the start.
+    ///
+    /// The instruction pointer is set to the entry address, see [Self::new_at], the machine is no longer [halted](Self::halted),
+    /// the [cycles](Self::cycles) and [branch statistics](Self::branches_taken) are reset to 0
+    /// and the history used by [Self::step_back] is cleared.
+    /// If a memory was saved with [Self::save_initial_memory], it is restored as well.
+    /// Breakpoints and watchpoints are kept.
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")] {
+    /// # use qelbus::{Memory, Subleq, memory::LinearMemory};
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[6, 7, 3, 8, 8, -1, 2, 5]);
+    /// subleq.save_initial_memory();
+    ///
+    /// subleq.run().unwrap();
+    /// assert_eq!(subleq.memory.get(&7), Ok(&3));
+    ///
+    /// subleq.reset();
+    /// assert!(!subleq.halted());
+    /// assert_eq!(subleq.cycles(), 0);
+    /// assert_eq!(subleq.memory.get(&7), Ok(&5));
+    ///
+    /// subleq.memory.set(&6, 1).unwrap();
+    /// subleq.run().unwrap();
+    /// assert_eq!(subleq.memory.get(&7), Ok(&4));
+    /// # }
+    /// ```
+    pub fn reset(&mut self) {
+        #[cfg(feature = "std")]
+        if let Some(initial_memory) = &self.initial_memory {
+            self.memory = M::clone(initial_memory);
+        }
+        self.curr_instruction = self.entry.clone();
+        self.cycles = 0;
+        self.reset_branch_stats();
+        self.halted = false;
+        #[cfg(feature = "std")]
+        self.history.clear();
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, M> Subleq<T, M>
+where
+    T: Word,
+    M: Memory<T>,
+{
+    /// Get the values of the cells from address 0 on, the inverse of [Self::with_program].
+    ///
+    /// For a memory which knows its size, see [Memory::size_hint], the image contains every cell.
+    /// Otherwise it ends at the highest non-negative address yielded by [Memory::iter_cells],
+    /// so it is empty for memories which can't list their cells.
+    ///
+    /// ```
+    /// # use qelbus::{Memory, Subleq, memory::{LinearMemory, SparseMemory}};
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 8>>::with_program(&[6, 7, 3, 8, 8, -1, 2, 5]);
+    /// subleq.step().unwrap();
+    /// assert_eq!(subleq.memory_image().unwrap(), [6, 7, 3, 8, 8, -1, 2, 3]);
+    ///
+    /// let mut memory = SparseMemory::new();
+    /// memory.set(&2, 4).unwrap();
+    /// memory.set(&-1, 1).unwrap();
+    /// assert_eq!(Subleq::new(memory).into_image().unwrap(), [0, 0, 4]);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an [Memory::Error] when getting from [Memory] fails.
+    /// The error type is specific to the [Memory] implementation.
+    pub fn memory_image(&self) -> Result<Vec<T>, M::Error> {
+        if let Some(size) = self.memory.size_hint() {
+            return self.memory.get_range(&T::zero(), size);
+        }
+
+        let mut image = Vec::new();
+        let Some(end) = self
+            .memory
+            .iter_cells()
+            .map(|(address, _)| address)
+            .filter(|address| *address >= T::zero())
+            .max()
+        else {
+            return Ok(image);
+        };
+        let mut address = T::zero();
+        loop {
+            image.push(self.memory.get(&address)?.clone());
+            if address == end {
+                return Ok(image);
+            }
+            address = address.wrapping_add(&T::one());
+        }
+    }
+
+    /// Consume the machine and return the values of its cells, see [Self::memory_image].
+    ///
+    /// This is also available as `TryFrom<Subleq>` for `Vec`.
+    ///
+    /// # Errors
+    /// Returns an [Memory::Error] when getting from [Memory] fails.
+    /// The error type is specific to the [Memory] implementation.
+    pub fn into_image(self) -> Result<Vec<T>, M::Error> {
+        self.memory_image()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, M> TryFrom<Subleq<T, M>> for Vec<T>
+where
+    T: Word,
+    M: Memory<T>,
+{
+    type Error = M::Error;
+
+    fn try_from(subleq: Subleq<T, M>) -> Result<Self, Self::Error> {
+        subleq.into_image()
+    }
+}
+
+impl<T, const SIZE: usize> Subleq<T, memory::LinearMemory<T, SIZE>>
+where
+    T: Word + AsPrimitive<usize> + AsPrimitive<i64>,
+{
+    /// Construct a new [Subleq] struct with a program loaded at the start of a [memory::LinearMemory].
+    ///
+    /// ```
+    /// # use qelbus::{Subleq, memory::LinearMemory};
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 256>>::with_program(&[3, 3, -1]);
+    /// subleq.run().unwrap();
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if the program is longer than `SIZE`, see [memory::LinearMemory::from_slice].
+    pub fn with_program(data: &[T]) -> Self {
+        match memory::LinearMemory::from_slice(data) {
+            Ok(memory) => Self::new(memory),
+            Err(error) => panic!("{error}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const SIZE: usize> Subleq<i32, memory::LinearMemory<i32, SIZE>> {
+    /// Construct a new [Subleq] struct with a program parsed from whitespace separated integers.
+    ///
+    /// This is also available as `TryFrom<&str>`.
+    /// For labels and comments, see [asm::assemble].
+    ///
+    /// ```
+    /// # use qelbus::{ParseError, ParseErrorKind, Subleq, memory::LinearMemory};
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::parse("3 3 -1").unwrap();
+    /// subleq.run().unwrap();
+    ///
+    /// assert_eq!(
+    ///     Subleq::<i32, LinearMemory<i32, 16>>::parse("3 three -1"),
+    ///     Err(ParseError {
+    ///         line: 1,
+    ///         column: 3,
+    ///         kind: ParseErrorKind::InvalidToken("three".to_string()),
+    ///     })
+    /// );
+    /// assert_eq!(
+    ///     Subleq::<i32, LinearMemory<i32, 16>>::parse("3 3\n9999999999"),
+    ///     Err(ParseError {
+    ///         line: 2,
+    ///         column: 1,
+    ///         kind: ParseErrorKind::Overflow("9999999999".to_string()),
+    ///     })
+    /// );
+    /// assert_eq!(
+    ///     Subleq::<i32, LinearMemory<i32, 2>>::parse("3 3\n -1 0"),
+    ///     Err(ParseError {
+    ///         line: 2,
+    ///         column: 2,
+    ///         kind: ParseErrorKind::ProgramTooLarge { len: 4, capacity: 2 },
+    ///     })
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// Returns a [ParseError] with the position of the first token which isn't an `i32`
+    /// or of the first token which doesn't fit in the memory.
+    pub fn parse(src: &str) -> Result<Self, ParseError> {
+        let mut program = Vec::new();
+        let mut first_excess = None;
+        for (line_index, line) in src.lines().enumerate() {
+            for token in line.split_whitespace() {
+                let position = (line_index + 1, token_column(line, token));
+                let value = parse_integer(token).map_err(|kind| ParseError {
+                    line: position.0,
+                    column: position.1,
+                    kind,
+                })?;
+                if program.len() == SIZE {
+                    first_excess = Some(position);
+                }
+                program.push(value);
+            }
+        }
+
+        if let Some((line, column)) = first_excess {
+            return Err(ParseError {
+                line,
+                column,
+                kind: ParseErrorKind::ProgramTooLarge {
+                    len: program.len(),
+                    capacity: SIZE,
+                },
+            });
+        }
+        Ok(Self::with_program(&program))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const SIZE: usize> TryFrom<&str> for Subleq<i32, memory::LinearMemory<i32, SIZE>> {
+    type Error = ParseError;
+
+    fn try_from(src: &str) -> Result<Self, Self::Error> {
+        Self::parse(src)
+    }
+}
+
+/// The magic number at the start of a snapshot written by [Subleq::write_snapshot].
+#[cfg(feature = "std")]
+const SNAPSHOT_MAGIC: [u8; 4] = *b"SQSN";
+
+/// The version of the snapshot format written by [Subleq::write_snapshot].
+#[cfg(feature = "std")]
+const SNAPSHOT_VERSION: u16 = 2;
+
+/// The bit of the word tag in a snapshot which is set for signed words.
+#[cfg(feature = "std")]
+const SNAPSHOT_SIGNED: u8 = 0x80;
+
+/// Get the tag of the word type in a snapshot, which is its width in bytes with [SNAPSHOT_SIGNED] set for signed words.
+#[cfg(feature = "std")]
+fn snapshot_word_tag<T>() -> u8
+where
+    T: Word + num::traits::ToBytes,
+{
+    let width = core::mem::size_of::<<T as num::traits::ToBytes>::Bytes>() as u8;
+    // Only signed words wrap around to a value below 0.
+    if T::zero().wrapping_sub(&T::one()) < T::zero() {
+        width | SNAPSHOT_SIGNED
+    } else {
+        width
+    }
+}
+
+/// Describe the word type of a snapshot tag for error messages.
+#[cfg(feature = "std")]
+fn describe_snapshot_word_tag(tag: u8) -> String {
+    let signedness = if tag & SNAPSHOT_SIGNED == 0 {
+        "unsigned"
+    } else {
+        "signed"
+    };
+    format!("{signedness} words of {} bytes", tag & !SNAPSHOT_SIGNED)
+}
+
+#[cfg(feature = "std")]
+impl<T, const SIZE: usize> Subleq<T, memory::LinearMemory<T, SIZE>>
+where
+    T: Word
+        + AsPrimitive<usize>
+        + AsPrimitive<i64>
+        + num::traits::ToBytes
+        + num::traits::FromBytes<Bytes = <T as num::traits::ToBytes>::Bytes>,
+{
+    /// Write the instruction pointer and the memory in a versioned binary format,
+    /// which [Self::read_snapshot] reads back.
+    ///
+    /// All integers are little-endian:
+    ///
+    /// | bytes          | content                                      |
+    /// |----------------|----------------------------------------------|
+    /// | 4              | the magic number `SQSN`                      |
+    /// | 2              | the format version, currently 2              |
+    /// | 1              | the width `w` of a word in bytes, plus `0x80` if it is signed |
+    /// | `w`            | the instruction pointer                      |
+    /// | 8              | the amount of cells `n`                      |
+    /// | `n * w`        | the cells, see [memory::LinearMemory::dump_le] |
+    ///
+    /// The counters, the history and the settings of the machine aren't saved, see [Self::snapshot] for those.
+    ///
+    /// ```
+    /// # use qelbus::{Memory, Subleq, memory::LinearMemory};
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[6, 7, 3, 8, 8, -1, 2, 5]);
+    /// subleq.step().unwrap();
+    ///
+    /// let mut bytes = Vec::new();
+    /// subleq.write_snapshot(&mut bytes).unwrap();
+    /// assert_eq!(bytes[..7], [b'S', b'Q', b'S', b'N', 2, 0, 4 | 0x80]);
+    ///
+    /// let restored = Subleq::<i32, LinearMemory<i32, 16>>::read_snapshot(&mut &bytes[..]).unwrap();
+    /// assert_eq!(restored.curr_instruction, 3);
+    /// assert_eq!(restored.memory, subleq.memory);
+    ///
+    /// // The words of this snapshot are 4 bytes wide and signed.
+    /// assert!(Subleq::<i64, LinearMemory<i64, 16>>::read_snapshot(&mut &bytes[..]).is_err());
+    /// assert!(Subleq::<u32, LinearMemory<u32, 16>>::read_snapshot(&mut &bytes[..]).is_err());
+    /// ```
+    ///
+    /// # Errors
+    /// Returns the error of `writer` if writing fails.
+    pub fn write_snapshot<W>(&self, writer: &mut W) -> std::io::Result<()>
+    where
+        W: std::io::Write,
+    {
+        writer.write_all(&SNAPSHOT_MAGIC)?;
+        writer.write_all(&SNAPSHOT_VERSION.to_le_bytes())?;
+        writer.write_all(&[snapshot_word_tag::<T>()])?;
+        writer.write_all(self.curr_instruction.to_le_bytes().as_ref())?;
+        writer.write_all(&(SIZE as u64).to_le_bytes())?;
+        self.memory.dump_le(writer)
+    }
+
+    /// Construct a new [Subleq] struct from a snapshot written by [Self::write_snapshot].
+    ///
+    /// A snapshot with fewer than `SIZE` cells fills the start of the memory, the remaining cells are 0.
+    /// The machine starts execution at the saved instruction pointer, [Self::reset] still returns to the first address.
+    ///
+    /// # Errors
+    /// Returns the error of `reader` if reading fails.
+    /// Returns an error of kind [std::io::ErrorKind::InvalidData] if the magic number is wrong,
+    /// the version, word width or signedness differ from the ones written by [Self::write_snapshot]
+    /// or there are more than `SIZE` cells.
+    /// Returns an error of kind [std::io::ErrorKind::UnexpectedEof] if the snapshot is truncated.
+    pub fn read_snapshot<R>(reader: &mut R) -> std::io::Result<Self>
+    where
+        R: std::io::Read,
+    {
+        let invalid =
+            |message: String| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+
+        let mut header = [0; 7];
+        reader.read_exact(&mut header)?;
+        if header[..4] != SNAPSHOT_MAGIC {
+            return Err(invalid("not a subleq snapshot".to_owned()));
+        }
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if version != SNAPSHOT_VERSION {
+            return Err(invalid(format!(
+                "snapshot version {version} is not supported, expected {SNAPSHOT_VERSION}"
+            )));
+        }
+        let tag = snapshot_word_tag::<T>();
+        if header[6] != tag {
+            return Err(invalid(format!(
+                "snapshot has {}, expected {}",
+                describe_snapshot_word_tag(header[6]),
+                describe_snapshot_word_tag(tag)
+            )));
+        }
+        let width = core::mem::size_of::<<T as num::traits::ToBytes>::Bytes>();
+
+        let mut curr_instruction = T::zero().to_le_bytes();
+        reader.read_exact(curr_instruction.as_mut())?;
+
+        let mut len = [0; 8];
+        reader.read_exact(&mut len)?;
+        let len = u64::from_le_bytes(len);
+        if len > SIZE as u64 {
+            return Err(invalid(format!(
+                "snapshot has {len} cells, but the memory has {SIZE} cells"
+            )));
+        }
+        let mut cells = vec![0; len as usize * width];
+        reader.read_exact(&mut cells)?;
+
+        let mut subleq = Self::new(memory::LinearMemory::load_le(&mut &cells[..])?);
+        subleq.curr_instruction = T::from_le_bytes(&curr_instruction);
+        Ok(subleq)
+    }
+}
+
+/// Parse a program in the `.sq` format into the values of its cells.
+///
+/// The values are integers separated by commas and whitespace.
+/// Everything after a `#` up to the end of the line is a comment.
+///
+/// ```
+/// # use qelbus::{ParseError, ParseErrorKind, parse_sq};
+/// let program = parse_sq::<i32>("
+///     ## clear cell 3, then halt
+///     3, 3, 3,
+///     0, 0, -1
+/// ").unwrap();
+/// assert_eq!(program, [3, 3, 3, 0, 0, -1]);
+///
+/// assert_eq!(
+///     parse_sq::<i8>("1, 2,\n3, 1000"),
+///     Err(ParseError {
+///         line: 2,
+///         column: 4,
+///         kind: ParseErrorKind::Overflow("1000".to_string()),
+///     })
+/// );
+/// ```
+///
+/// # Errors
+/// Returns a [ParseError] with the position of the first token which isn't a `T`.
+#[cfg(feature = "std")]
+pub fn parse_sq<T>(src: &str) -> Result<Vec<T>, ParseError>
+where
+    T: core::str::FromStr<Err = core::num::ParseIntError>,
+{
+    let mut program = Vec::new();
+    for (line_index, line) in src.lines().enumerate() {
+        let code = line.split('#').next().unwrap_or_default();
+
+        for token in code.split(|c: char| c.is_ascii_whitespace() || c == ',') {
+            if !token.is_empty() {
+                let value = parse_integer(token).map_err(|kind| ParseError {
+                    line: line_index + 1,
+                    column: token_column(line, token),
+                    kind,
+                })?;
+                program.push(value);
+            }
+        }
+    }
+    Ok(program)
+}
+
+/// Get the column of `token`, which is a substring of `line`, as a byte offset starting at 1.
+#[cfg(feature = "std")]
+pub(crate) fn token_column(line: &str, token: &str) -> usize {
+    token.as_ptr() as usize - line.as_ptr() as usize + 1
+}
+
+/// Parse a single integer token, distinguishing overflowing integers from invalid tokens.
+#[cfg(feature = "std")]
+fn parse_integer<T>(token: &str) -> Result<T, ParseErrorKind>
+where
+    T: core::str::FromStr<Err = core::num::ParseIntError>,
+{
+    token
+        .parse()
+        .map_err(|error: core::num::ParseIntError| match error.kind() {
+            core::num::IntErrorKind::PosOverflow | core::num::IntErrorKind::NegOverflow => {
+                ParseErrorKind::Overflow(token.to_string())
+            }
+            _ => ParseErrorKind::InvalidToken(token.to_string()),
+        })
+}
+
+/// Convert a program to a wider word type, keeping the value of every cell.
+///
+/// Signed words are sign-extended, so negative halt addresses like `-1` stay halt addresses.
+/// Unsigned words halt at their maximum value, which isn't the maximum of a wider type,
+/// so halt addresses in unsigned programs have to be adjusted by hand.
+///
+/// ```
+/// # use qelbus::{Machine, Memory, convert_program};
+/// let program: [i16; 8] = [6, 7, 3, 8, 8, -1, 2, 5];
+/// let mut machine = Machine::load(&convert_program(&program));
+/// machine.run().unwrap();
+/// assert_eq!(machine.memory.get(&7), Ok(&3));
+/// assert_eq!(machine.curr_instruction, -1);
+/// ```
+#[cfg(feature = "std")]
+pub fn convert_program<T, U>(program: &[U]) -> Vec<T>
+where
+    U: Clone + Into<T>,
+{
+    program.iter().cloned().map(Into::into).collect()
+}
+
+/// Generate the source of a standalone Rust program which runs `program` and prints the result.
+///
+/// The generated `main` loads the program into a [memory::LinearMemory] as large as the one of a [Machine],
+/// or as large as the program if it doesn't fit.
+/// Then it runs the program and prints the amount of executed instructions and the cells the program occupies.
+/// It only needs this crate as a dependency, which makes it handy for teaching and reproducible bug reports.
+///
+/// ```
+/// # use qelbus::emit_rust;
+/// assert_eq!(
+///     emit_rust(&[3, 3, -1]),
+///     "use qelbus::{Subleq, memory::LinearMemory};
+///
+/// fn main() {
+///     let program: [i32; 3] = [3, 3, -1];
+///     let mut subleq = Subleq::<i32, LinearMemory<i32, 65536>>::with_program(&program);
+///     let report = subleq.run().unwrap();
+///     println!(\"executed {} instructions\", report.steps);
+///     println!(\"{:?}\", &subleq.memory.as_slice()[..program.len()]);
+/// }
+/// ",
+/// );
+/// ```
+#[cfg(feature = "std")]
+pub fn emit_rust<T>(program: &[T]) -> String
+where
+    T: Word + AsPrimitive<usize> + AsPrimitive<i64> + core::fmt::Display,
+{
+    use core::fmt::Write;
+
+    let word = core::any::type_name::<T>();
+    let len = program.len();
+    let size = len.max(MACHINE_SIZE);
+    let mut cells = String::new();
+    for (index, cell) in program.iter().enumerate() {
+        if index > 0 {
+            cells.push_str(", ");
+        }
+        // Writing to a String never fails.
+        let _ = write!(cells, "{cell}");
+    }
+
+    format!(
+        "use qelbus::{{Subleq, memory::LinearMemory}};
+
+fn main() {{
+    let program: [{word}; {len}] = [{cells}];
+    let mut subleq = Subleq::<{word}, LinearMemory<{word}, {size}>>::with_program(&program);
+    let report = subleq.run().unwrap();
+    println!(\"executed {{}} instructions\", report.steps);
+    println!(\"{{:?}}\", &subleq.memory.as_slice()[..program.len()]);
+}}
+"
+    )
+}
+
+/// Run a program with the I/O convention of [IoMemory](memory::IoMemory) and return its output.
+///
+/// The program is loaded at the start of a [Machine] sized memory.
+/// It runs until it halts or `max_steps` instructions have been executed,
+/// either way the output written so far is returned.
+///
+/// ```
+/// # use qelbus::run_program;
+/// // Echo bytes until the end of the input.
+/// let program = [18, 18, 3, -1, 18, 6, 18, 19, 12, 20, 20, -1, 18, -1, 15, 19, 19, 0];
+/// assert_eq!(run_program(&program, b"hi", 1_000).unwrap(), b"hi");
+/// assert_eq!(run_program(&program, b"hi", 5).unwrap(), b"h");
+/// ```
+///
+/// # Errors
+/// Returns an [Error](memory::Error) when the program accesses an invalid address
+/// or [Error::ProgramTooLarge](memory::Error::ProgramTooLarge) if it doesn't fit in the memory.
+#[cfg(feature = "std")]
+pub fn run_program(
+    program: &[i32],
+    input: &[u8],
+    max_steps: u64,
+) -> Result<Vec<u8>, memory::Error> {
+    let memory = memory::LinearMemory::<i32, MACHINE_SIZE>::from_slice(program)?;
+    let mut subleq = Subleq::new(memory::IoMemory::new(memory, input));
+    subleq.run_capped(max_steps)?;
+    Ok(subleq.memory.take_output())
+}
+
+/// The amount of cells in the memory of a [Machine].
+const MACHINE_SIZE: usize = 65536;
+
+/// A [Subleq] machine with 32-bit words and a [memory::LinearMemory] of 65536 cells,
+/// which fits most programs.
+///
+/// ```
+/// # use qelbus::{Machine, Memory};
+/// let mut machine = Machine::load(&[6, 7, 3, 8, 8, -1, 2, 5]);
+/// machine.run().unwrap();
+/// assert_eq!(machine.memory.get(&7), Ok(&3));
+/// ```
+pub type Machine = Subleq<i32, memory::LinearMemory<i32, MACHINE_SIZE>>;
+
+impl Machine {
+    /// Construct a new [Machine] with a program loaded at the start of its memory,
+    /// like [Subleq::with_program].
+    ///
+    /// # Panics
+    /// Panics if the program is longer than 65536 cells.
+    pub fn load(program: &[i32]) -> Self {
+        Self::with_program(program)
+    }
+}
+
+/// How a [Subleq] machine decodes instructions and where they branch to, see [Subleq::set_mode].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Mode<T> {
+    /// Instructions have three arguments A, B and C and branch to C.
+    #[default]
+    Subleq3,
+    /// Instructions have two arguments A and B and branch to a fixed address.
+    /// Otherwise execution continues with the instruction two cells further.
+    Subleq2 {
+        /// The address every instruction branches to.
+        target: T,
+    },
+}
+
+/// What a [Subleq] machine does when the subtraction of an instruction overflows the word type,
+/// see [Subleq::set_overflow_mode].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OverflowMode {
+    /// The difference wraps around.
+    #[default]
+    Wrap,
+    /// Executing the instruction fails with the error from [Memory::overflow_error],
+    /// before anything is written.
+    Error,
+    /// The difference is clamped to the range of the word type.
+    Saturate,
+}
+
+/// The outcome of executing a single instruction with [Subleq::step].
+///
+/// A clean halt is an outcome rather than an error,
+/// so a loop over [Subleq::step] can tell it apart from a failing [Memory].
+///
+/// ```
+/// # use qelbus::{StepOutcome, Subleq, memory::{Error, LinearMemory}};
+/// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[6, 6, 3, 6, 6, -1]);
+/// while let Ok(StepOutcome::Continued) = subleq.step() {}
+/// assert!(subleq.halted());
+///
+/// // Jumps out of the memory instead of halting.
+/// let mut subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[3, 3, 100]);
+/// let error = loop {
+///     match subleq.step() {
+///         Ok(StepOutcome::Continued) => {}
+///         Ok(StepOutcome::Halted | StepOutcome::IpOutOfBounds) => unreachable!(),
+///         Err(error) => break error,
+///     }
+/// };
+/// assert_eq!(error, Error::AddressOutOfRange(100));
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum StepOutcome {
+    /// The program continues with the next instruction.
+    Continued,
+    /// The program jumped to a halt address and has halted.
+    Halted,
+    /// Nothing was executed, because the instruction pointer lies above the maximum set with
+    /// [Subleq::set_max_ip] and the memory has no error for this, see [Memory::instruction_pointer_error].
+    ///
+    /// The program hasn't halted, its control flow went wrong.
+    IpOutOfBounds,
+}
+
+impl StepOutcome {
+    /// Check whether the program has halted.
+    pub fn is_halted(self) -> bool {
+        self == Self::Halted
+    }
+}
+
+/// The cell written by an instruction executed with [Subleq::step_observed].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Write<T> {
+    /// The address of the cell, which is argument B of the instruction.
+    pub address: T,
+    /// The value of the cell before the instruction was executed.
+    pub old: T,
+    /// The value written to the cell.
+    pub new: T,
+    /// Whether the instruction branched to argument C.
+    pub branched: bool,
+}
+
+/// Everything about an instruction which is being executed, passed to the hook of [Subleq::execute].
+#[derive(Clone)]
+struct Execution<T> {
+    /// The address of the instruction.
+    address: T,
+    /// The arguments of the instruction.
+    instruction: Instruction<T>,
+    /// The value read from A.
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    a_value: T,
+    /// The value read from B.
+    b_value: T,
+    /// The result of the subtraction which is going to be stored at B.
+    result: T,
+    /// Whether the instruction branches to C.
+    branch: bool,
+}
+
+/// What executing an instruction would do, see [Subleq::simulate_step].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct StepEffect<T> {
+    /// The address of B, which the result is stored at.
+    pub address: T,
+    /// The result which is stored at B.
+    pub value: T,
+    /// The instruction pointer after the instruction.
+    pub next_instruction: T,
+    /// Whether the instruction branches to C.
+    pub branched: bool,
+    /// Whether the subtraction overflows in [OverflowMode::Error],
+    /// so [Subleq::step] would return an error instead of storing the wrapped [Self::value].
+    pub overflowed: bool,
+}
+
+/// An iterator which executes one instruction per iteration, see [Subleq::iter].
+#[derive(Debug)]
+pub struct StepIter<'a, T, M>
+where
+    T: Word,
+    M: Memory<T>,
+{
+    /// The machine which executes the instructions.
+    subleq: &'a mut Subleq<T, M>,
+    /// Whether an error was returned or the instruction pointer went out of bounds, which ends the iteration.
+    failed: bool,
+    /// The amount of instructions left to execute before the iteration ends, see [Subleq::iter_capped].
+    remaining: Option<u64>,
+}
+
+impl<T, M> Iterator for StepIter<'_, T, M>
+where
+    T: Word,
+    M: Memory<T>,
+{
+    type Item = Result<(T, (T, T, T)), M::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed
+            || self.remaining == Some(0)
+            || self.subleq.halted
+            || self.subleq.curr_instruction.is_halt_address()
+        {
+            return None;
+        }
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= 1;
+        }
+
+        let address = self.subleq.curr_instruction.clone();
+        let item = self
+            .subleq
+            .peek_instruction()
+            .and_then(|instruction| Ok((self.subleq.step()?, instruction)));
+        match item {
+            Ok((StepOutcome::IpOutOfBounds, _)) => {
+                self.failed = true;
+                None
+            }
+            Ok((_, instruction)) => Some(Ok((address, instruction))),
+            Err(error) => {
+                self.failed = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+/// A saved state of a [Subleq] machine, see [Subleq::snapshot].
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Snapshot<T, M> {
+    /// The saved memory.
+    memory: M,
+    /// The saved instruction pointer.
+    curr_instruction: T,
+    /// The saved amount of executed instructions.
+    cycles: u64,
+    /// The saved amount of executed instructions which branched.
+    branches_taken: u64,
+    /// The saved amount of executed instructions which didn't branch.
+    branches_not_taken: u64,
+    /// Whether the saved machine had halted.
+    halted: bool,
+}
+
+/// A function deciding whether a program halts, given argument C and the instruction pointer.
+#[cfg(feature = "std")]
+type HaltFn<T> = dyn Fn(&T, &T) -> bool + Send + Sync;
+
+/// A predicate set with [Subleq::set_halt_predicate], which compares by identity.
+#[cfg(feature = "std")]
+#[derive(Clone)]
+struct HaltPredicate<T>(std::sync::Arc<HaltFn<T>>);
+
+#[cfg(feature = "std")]
+impl<T> PartialEq for HaltPredicate<T> {
+    fn eq(&self, other: &Self) -> bool {
+        std::sync::Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Eq for HaltPredicate<T> {}
+
+#[cfg(feature = "std")]
+impl<T> core::hash::Hash for HaltPredicate<T> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        std::sync::Arc::as_ptr(&self.0).cast::<()>().hash(state);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> core::fmt::Debug for HaltPredicate<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("HaltPredicate")
+    }
+}
+
+/// A function called when a program halts, see [Subleq::set_halt_handler].
+#[cfg(feature = "std")]
+type HaltHandlerFn<T, M> = dyn FnMut(&mut Subleq<T, M>) -> HaltAction + Send;
+
+/// A handler set with [Subleq::set_halt_handler], which compares by identity.
+///
+/// The handler is shared between clones of a machine.
+#[cfg(feature = "std")]
+struct HaltHandler<T, M>(std::sync::Arc<std::sync::Mutex<Box<HaltHandlerFn<T, M>>>>)
+where
+    T: Word,
+    M: Memory<T>;
+
+#[cfg(feature = "std")]
+impl<T, M> Clone for HaltHandler<T, M>
+where
+    T: Word,
+    M: Memory<T>,
+{
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, M> PartialEq for HaltHandler<T, M>
+where
+    T: Word,
+    M: Memory<T>,
+{
+    fn eq(&self, other: &Self) -> bool {
+        std::sync::Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, M> Eq for HaltHandler<T, M>
+where
+    T: Word,
+    M: Memory<T>,
+{
+}
+
+#[cfg(feature = "std")]
+impl<T, M> core::hash::Hash for HaltHandler<T, M>
+where
+    T: Word,
+    M: Memory<T>,
+{
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        std::sync::Arc::as_ptr(&self.0).hash(state);
+    }
+}
+
+/// What happens after a program halted, returned by the handler set with [Subleq::set_halt_handler].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum HaltAction {
+    /// Execution stops and the machine stays halted.
+    Stop,
+    /// Execution continues at the instruction pointer.
+    Resume,
+}
+
+/// The change made by a single executed instruction, used to undo it.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Change<T> {
+    /// The address the instruction wrote to.
+    address: T,
+    /// The value at [Self::address] before the instruction was executed.
+    old_value: T,
+    /// The instruction pointer before the instruction was executed.
+    previous_instruction: T,
+    /// Whether the instruction branched, which is counted in [Subleq::branches_taken].
+    branched: bool,
+}
+
+/// What a call to [Subleq::run] did.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct RunReport {
+    /// The amount of instructions which have been executed.
+    pub steps: u64,
+    /// Why execution stopped.
+    pub reason: RunResult,
+}
+
+/// The reason [Subleq::run] or [Subleq::run_capped] stopped executing.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum RunResult {
+    /// The program has halted.
+    Halted,
+    /// The maximum amount of instructions has been executed without the program halting.
+    LimitReached,
+    /// The instruction pointer went above the maximum, see [StepOutcome::IpOutOfBounds].
+    IpOutOfBounds,
+}
+
+/// An error while parsing a program with [Subleq::parse] or [parse_sq], at the position of the token which caused it.
+#[cfg(feature = "std")]
+#[derive(thiserror::Error, Clone, Eq, PartialEq, Hash, Debug)]
+#[error("line {line}, column {column}: {kind}")]
+pub struct ParseError {
+    /// The line of the token, starting at 1.
+    pub line: usize,
+    /// The byte offset of the token in its line, starting at 1.
+    pub column: usize,
+    /// What is wrong with the token.
+    pub kind: ParseErrorKind,
+}
+
+/// The kind of a [ParseError].
+#[cfg(feature = "std")]
+#[derive(thiserror::Error, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum ParseErrorKind {
+    /// A token isn't an integer.
+    #[error("invalid token `{0}`")]
+    InvalidToken(String),
+    /// A token is an integer, but doesn't fit in the word type.
+    #[error("integer `{0}` does not fit in the word type")]
+    Overflow(String),
+    /// The program has more cells than the memory, reported at the first token which doesn't fit.
+    #[error("program of length {len} does not fit in a memory of size {capacity}")]
+    ProgramTooLarge {
+        /// The amount of cells in the program.
+        len: usize,
+        /// The amount of cells in the memory.
+        capacity: usize,
+    },
+}
+
+/// An error returned by [Subleq::run_tracing].
+#[cfg(feature = "std")]
+#[derive(thiserror::Error, Debug)]
+pub enum TraceError<E> {
+    /// Getting or setting [Memory] failed.
+    #[error(transparent)]
+    Memory(E),
+    /// Writing the trace failed.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// The reason [Subleq::run_to_breakpoint] stopped executing.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum BreakReason<T> {
+    /// The instruction at a breakpoint is about to be executed.
+    Breakpoint(T),
+    /// An instruction has written to a watched address.
+    Watchpoint(T),
+    /// The program has halted.
+    Halted,
+    /// The instruction pointer went above the maximum, see [StepOutcome::IpOutOfBounds].
+    IpOutOfBounds,
+}
+
+/// The differences between two [Subleq] machines, see [Subleq::diff].
+#[cfg(feature = "std")]
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Diff<T> {
+    /// The address, the value in the first machine and the value in the second machine
+    /// of every cell which differs, ordered by address.
+    pub cells: Vec<(T, T, T)>,
+    /// The instruction pointers of both machines if they differ.
+    pub curr_instruction: Option<(T, T)>,
+}
+
+#[cfg(feature = "std")]
+impl<T> Diff<T> {
+    /// Whether the machines have the same state.
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty() && self.curr_instruction.is_none()
+    }
+}
+
+/// Represent an instruction.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Instruction<T> {
+    /// The subtrahend address.
+    pub a: T,
+    /// The minuend and store address.
+    pub b: T,
+    /// The address to jump to.
+    pub c: T,
+}
+
+/// Check whether the instruction `A B C` is the halt idiom emitted by assemblers, like `Z Z -1`.
+///
+/// An instruction where A equals B clears the cell and always branches,
+/// so it halts the program if C is a halt address, see [Word::is_halt_address].
+///
+/// ```
+/// # use qelbus::is_halt_instruction;
+/// assert!(is_halt_instruction(&8, &8, &-1));
+/// assert!(!is_halt_instruction(&8, &9, &-1));
+/// assert!(!is_halt_instruction(&8, &8, &0));
+/// ```
+pub fn is_halt_instruction<T>(a: &T, b: &T, c: &T) -> bool
+where
+    T: Word,
+{
+    a == b && c.is_halt_address()
+}
+
+/// What an instruction does, recognizing the idioms emitted by assemblers, see [classify_instruction].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum InstrKind {
+    /// A equals B and C is a halt address, so the instruction always halts, like `Z Z -1`.
+    Halt,
+    /// A equals B, so the instruction clears the cell and always jumps to C, like `Z Z loop`.
+    Clear,
+    /// The instruction subtracts and jumps to C if the result isn't positive.
+    Branch,
+    /// C is the address of the next instruction, so the instruction only subtracts.
+    Sub,
+}
+
+impl core::fmt::Display for InstrKind {
+    /// Writes the name of the kind in lowercase, like `halt`.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            Self::Halt => "halt",
+            Self::Clear => "clear",
+            Self::Branch => "branch",
+            Self::Sub => "sub",
+        })
+    }
+}
+
+/// Classify the instruction `A B C` at `address`, which [Subleq::run_tracing] and the disassembler annotate.
+///
+/// The address is needed to tell whether C is the next instruction,
+/// which assemblers emit for instructions which only subtract.
+///
+/// ```
+/// # use qelbus::{InstrKind, classify_instruction};
+/// assert_eq!(classify_instruction(&0, &8, &8, &-1), InstrKind::Halt);
+/// assert_eq!(classify_instruction(&0, &8, &8, &9), InstrKind::Clear);
+/// assert_eq!(classify_instruction(&0, &8, &9, &9), InstrKind::Branch);
+/// assert_eq!(classify_instruction(&0, &8, &9, &3), InstrKind::Sub);
+/// ```
+pub fn classify_instruction<T>(address: &T, a: &T, b: &T, c: &T) -> InstrKind
+where
+    T: Word,
+{
+    if is_halt_instruction(a, b, c) {
+        InstrKind::Halt
+    } else if a == b {
+        InstrKind::Clear
+    } else if *c == address.wrapping_add(&word::three()) {
+        InstrKind::Sub
+    } else {
+        InstrKind::Branch
+    }
+}
+
+/// Represent a read- and writable Memory implementation.
+///
+/// Example implementation
+/// ```
+/// # use qelbus::Memory;
+/// struct ByteMemory([i8; 256]);
+///
+/// impl Memory<i8> for ByteMemory {
+///   type Error = std::convert::Infallible;
+///
+///   fn get(&self, index: &i8) -> Result<&i8, Self::Error> {
+///     Ok(&self.0[*index as u8 as usize])
+///   }
+///
+///   fn set(&mut self, index: &i8, value: i8) -> Result<(), Self::Error> {
+///     self.0[*index as u8 as usize] = value;
+///     Ok(())
+///   }
+/// }
+/// ```
+pub trait Memory<T>
+where
+    T: Word,
+{
+    /// An error while using the memory
+    type Error: core::error::Error;
+
+    /// Get the value at an address or return an error.
+    ///
+    /// # Errors
+    /// Errors are implementation-specific, see [Self::Error].
+    fn get(&self, index: &T) -> Result<&T, Self::Error>;
+
+    /// Load the value at an address for an executing instruction or return an error.
+    ///
+    /// Unlike [Self::get], loading a value may have side effects, like consuming input.
+    /// [Subleq::step] uses this to read the values of the A and B arguments.
+    ///
+    /// The provided implementation calls [Self::get].
+    ///
+    /// # Errors
+    /// Errors are implementation-specific, see [Self::Error].
+    fn load(&mut self, index: &T) -> Result<T, Self::Error> {
+        Ok(self.get(index)?.clone())
+    }
+
+    /// Get the instruction at an address or return an error.
+    ///
+    /// The provided implementation calls [Self::get].
+    /// If the arguments B or C would lie past the end of the address space,
+    /// it returns the error from [Self::address_overflow_error] instead of wrapping around.
+    /// Use [Self::instruction_wrapping] to wrap around deliberately.
+    ///
+    /// ```
+    /// # use qelbus::{Instruction, Memory, memory::{Error, LinearMemory}};
+    /// let memory = LinearMemory::<u8, 256>::new();
+    /// assert_eq!(memory.instruction(&254), Err(Error::AddressOutOfRange(256)));
+    /// assert_eq!(memory.instruction_wrapping(&254), Ok(Instruction { a: 0, b: 0, c: 0 }));
+    /// ```
+    ///
+    /// # Errors
+    /// Errors are implementation-specfific, see [Self::Error].
+    #[inline]
+    fn instruction(&self, index: &T) -> Result<Instruction<T>, Self::Error> {
+        match index.checked_add(&word::two()) {
+            Some(_) => self.instruction_wrapping(index),
+            None => match self.address_overflow_error(index) {
+                Some(error) => Err(error),
+                None => self.instruction_wrapping(index),
+            },
+        }
+    }
+
+    /// Get the instruction at an address like [Self::instruction],
+    /// but wrap around to the start of the address space for arguments past its end.
+    ///
+    /// The provided implementation calls [Self::get].
+    ///
+    /// # Errors
+    /// Errors are implementation-specfific, see [Self::Error].
+    fn instruction_wrapping(&self, index: &T) -> Result<Instruction<T>, Self::Error> {
+        Ok(Instruction {
+            a: self.get(index)?.clone(),
+            b: self.get(&index.wrapping_add(&T::one()))?.clone(),
+            c: self.get(&index.wrapping_add(&word::two()))?.clone(),
+        })
+    }
+
+    /// Construct the error which [Self::instruction] returns when the arguments
+    /// of the instruction at `address` would lie past the end of the address space.
+    ///
+    /// The provided implementation returns `None`, for memories whose error type can't represent this.
+    /// [Self::instruction] wraps around for those memories.
+    fn address_overflow_error(&self, address: &T) -> Option<Self::Error> {
+        let _ = address;
+        None
+    }
+
+    /// Construct the error which [Subleq::step] returns when the instruction pointer `address`
+    /// lies above the maximum, see [Subleq::set_max_ip].
+    ///
+    /// The provided implementation returns `None`, for memories whose error type can't represent this.
+    /// [Subleq::step] halts for those memories.
+    fn instruction_pointer_error(&self, address: &T) -> Option<Self::Error> {
+        let _ = address;
+        None
+    }
+
+    /// Whether different addresses can refer to the same cell, so setting one changes the value of the other.
+    ///
+    /// Wrappers which cache by address, like [CachedMemory](memory::CachedMemory),
+    /// drop their whole cache on every write to a memory which aliases.
+    /// The provided implementation returns `false`, so memories which wrap addresses around have to override it.
+    fn aliases(&self) -> bool {
+        false
+    }
+
+    /// Set the value at an address or return an error.
+    ///
+    /// # Errors
+    /// Errors are implementation-specific, see [Self::Error].
     fn set(&mut self, index: &T, value: T) -> Result<(), Self::Error>;
+
+    /// Set the arguments A, B and C of the instruction at an address or return an error.
+    ///
+    /// The provided implementation calls [Self::set_range].
+    ///
+    /// ```
+    /// # use qelbus::{Instruction, Memory, memory::LinearMemory};
+    /// let mut memory = LinearMemory::<i32, 16>::new();
+    /// memory.set_instruction(&3, Instruction { a: 6, b: 6, c: -1 }).unwrap();
+    /// assert_eq!(memory.instruction(&3), Ok(Instruction { a: 6, b: 6, c: -1 }));
+    /// ```
+    ///
+    /// # Errors
+    /// Errors are implementation-specific, see [Self::Error].
+    fn set_instruction(
+        &mut self,
+        index: &T,
+        instruction: Instruction<T>,
+    ) -> Result<(), Self::Error> {
+        self.set_range(index, &[instruction.a, instruction.b, instruction.c])
+    }
+
+    /// Swap the values at two addresses or return an error.
+    ///
+    /// The provided implementation gets both values before setting them,
+    /// so nothing is changed if either address can't be read.
+    ///
+    /// ```
+    /// # use qelbus::{Memory, memory::{Error, LinearMemory}};
+    /// let mut memory = LinearMemory::<i32, 4>::from_slice(&[1, 2, 3, 4]).unwrap();
+    /// memory.swap(&0, &3).unwrap();
+    /// assert_eq!(memory.as_slice(), [4, 2, 3, 1]);
+    /// assert_eq!(memory.swap(&0, &4), Err(Error::AddressOutOfRange(4)));
+    /// ```
+    ///
+    /// # Errors
+    /// Errors are implementation-specific, see [Self::Error].
+    fn swap(&mut self, a: &T, b: &T) -> Result<(), Self::Error> {
+        let a_value = self.get(a)?.clone();
+        let b_value = self.get(b)?.clone();
+        self.set(a, b_value)?;
+        self.set(b, a_value)
+    }
+
+    /// Construct the error which [Subleq::step] returns in [OverflowMode::Error] when the subtraction
+    /// of the instruction at `address` overflows, where `a` and `b` are the values read from A and B.
+    ///
+    /// The instruction is aborted afterwards, so memories which track the progress of an instruction
+    /// can reset it here.
+    ///
+    /// The provided implementation returns `None`, for memories whose error type can't represent this.
+    /// The subtraction wraps around for those memories.
+    fn overflow_error(&mut self, address: &T, a: T, b: T) -> Option<Self::Error> {
+        let _ = (address, a, b);
+        None
+    }
+
+    /// Get the values of `len` consecutive cells starting at an address or return an error.
+    ///
+    /// The provided implementation calls [Self::get] for every cell.
+    /// It only reserves space for as many cells as the [size](Self::size_hint) of the memory,
+    /// so a huge `len` fails when reading a cell instead of when allocating.
+    ///
+    /// # Errors
+    /// Errors are implementation-specific, see [Self::Error].
+    #[cfg(feature = "std")]
+    fn get_range(&self, start: &T, len: usize) -> Result<Vec<T>, Self::Error> {
+        let mut values = Vec::with_capacity(self.size_hint().map_or(0, |size| len.min(size)));
+        let mut index = start.clone();
+        for _ in 0..len {
+            values.push(self.get(&index)?.clone());
+            index = index.wrapping_add(&T::one());
+        }
+        Ok(values)
+    }
+
+    /// Set the values of consecutive cells starting at an address or return an error.
+    ///
+    /// The provided implementation calls [Self::set] for every cell,
+    /// so the cells before the one which caused an error have already been set.
+    ///
+    /// # Errors
+    /// Errors are implementation-specific, see [Self::Error].
+    fn set_range(&mut self, start: &T, values: &[T]) -> Result<(), Self::Error> {
+        let mut index = start.clone();
+        for value in values {
+            self.set(&index, value.clone())?;
+            index = index.wrapping_add(&T::one());
+        }
+        Ok(())
+    }
+
+    /// Iterate over the addresses and values of the cells which are defined in the memory.
+    ///
+    /// What counts as defined is implementation-specific, for example
+    /// a memory where every address is backed by a cell yields all of them,
+    /// while a sparse memory may only yield the cells which have been written to.
+    ///
+    /// The provided implementation yields nothing, for memories which can't list their cells.
+    /// The iterator is boxed so [Memory] stays usable as a trait object.
+    ///
+    /// ```
+    /// # use qelbus::{Memory, memory::{Error, LinearMemory, VecMemory}};
+    /// let mut vec = VecMemory::with_max(16);
+    /// vec.set(&1, 7).unwrap();
+    /// let memories: [&dyn Memory<i32, Error = Error>; 2] =
+    ///     [&LinearMemory::<i32, 2>::from_slice(&[1, 2]).unwrap(), &vec];
+    /// let cells: Vec<Vec<_>> = memories.iter().map(|m| m.iter_cells().collect()).collect();
+    /// assert_eq!(cells, [vec![(0, 1), (1, 2)], vec![(0, 0), (1, 7)]]);
+    /// ```
+    #[cfg(feature = "std")]
+    fn iter_cells<'a>(&'a self) -> Box<dyn Iterator<Item = (T, T)> + 'a>
+    where
+        T: 'a,
+    {
+        Box::new(core::iter::empty())
+    }
+
+    /// Get the amount of cells in the memory, starting at address 0,
+    /// or `None` if the memory is unbounded or doesn't know its size.
+    ///
+    /// This lets generic code like a memory dump visit every cell of a bounded memory.
+    /// The provided implementation returns `None`.
+    fn size_hint(&self) -> Option<usize> {
+        None
+    }
+
+    /// Set every cell to 0, for example to run another program after a run.
+    ///
+    /// The provided implementation calls [Self::set] with 0 for the addresses below the [size](Self::size_hint),
+    /// or, for memories which don't know their size, for every cell yielded by [Self::iter_cells].
+    /// The cells before the one which caused an error have already been set.
+    /// Memories which can do this faster, or which neither know their size nor list their cells, should override it.
+    ///
+    /// ```
+    /// # use qelbus::{Memory, Subleq, memory::LinearMemory};
+    /// let mut subleq = Subleq::<i32, LinearMemory<i32, 4>>::with_program(&[3, 3, -1]);
+    /// subleq.run().unwrap();
+    /// subleq.memory.clear().unwrap();
+    /// subleq.reset();
+    /// assert_eq!(subleq.memory, LinearMemory::new());
+    /// ```
+    ///
+    /// # Errors
+    /// Errors are implementation-specific, see [Self::Error].
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        if let Some(size) = self.size_hint() {
+            let mut address = T::zero();
+            for _ in 0..size {
+                self.set(&address, T::zero())?;
+                address = address.wrapping_add(&T::one());
+            }
+        } else {
+            #[cfg(feature = "std")]
+            {
+                let addresses: Vec<T> = self.iter_cells().map(|(address, _)| address).collect();
+                for address in addresses {
+                    self.set(&address, T::zero())?;
+                }
+            }
+        }
+        Ok(())
+    }
 }
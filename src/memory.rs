@@ -0,0 +1,2739 @@
+//! Ready-made [Memory] implementations.
+
+use core::ops::RangeBounds;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+#[cfg(feature = "std")]
+use num::traits::{FromBytes, ToBytes};
+use num::{Zero, traits::AsPrimitive};
+
+use crate::{Memory, Word};
+
+/// An error while using one of the [Memory] implementations in this module.
+///
+/// Memories with their own error types can convert them with [Error::custom],
+/// which makes them usable with code expecting this error, like [Protected].
+#[derive(thiserror::Error, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Error {
+    /// The address lies outside of the memory.
+    #[error("address {0} is out of range")]
+    AddressOutOfRange(usize),
+    /// The address is negative, while the memory only has non-negative addresses.
+    #[error("address {0} is negative")]
+    NegativeAddress(i64),
+    /// The address can't be written to.
+    #[error("address {0} is read-only")]
+    ImmutableAddress(usize),
+    /// The instruction pointer lies above the maximum, see [Subleq::set_max_ip](crate::Subleq::set_max_ip).
+    #[error("instruction pointer {0} is out of bounds")]
+    InstructionPointerOutOfBounds(i64),
+    /// Subtracting A from B overflowed in [OverflowMode::Error](crate::OverflowMode::Error).
+    #[error("subtracting {a} from {b} overflows in the instruction at address {address}")]
+    Overflow {
+        /// The address of the instruction.
+        address: usize,
+        /// The value read from A.
+        a: i64,
+        /// The value read from B.
+        b: i64,
+    },
+    /// A program has more cells than the memory it is loaded into.
+    #[error("program of length {len} does not fit in a memory of size {capacity}")]
+    ProgramTooLarge {
+        /// The amount of cells in the program.
+        len: usize,
+        /// The amount of cells in the memory.
+        capacity: usize,
+    },
+    /// An error from outside of this crate, see [Error::custom].
+    #[cfg(feature = "std")]
+    #[error(transparent)]
+    Custom(CustomError),
+}
+
+#[cfg(feature = "std")]
+impl Error {
+    /// Wrap an arbitrary error in [Error::Custom].
+    ///
+    /// ```
+    /// # use qelbus::memory::Error;
+    /// #[derive(Debug)]
+    /// struct Disconnected;
+    ///
+    /// impl std::fmt::Display for Disconnected {
+    ///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    ///         f.write_str("the memory is disconnected")
+    ///     }
+    /// }
+    ///
+    /// impl std::error::Error for Disconnected {}
+    ///
+    /// impl From<Disconnected> for Error {
+    ///     fn from(error: Disconnected) -> Self {
+    ///         Error::custom(error)
+    ///     }
+    /// }
+    ///
+    /// let error = Error::from(Disconnected);
+    /// assert_eq!(error.to_string(), "the memory is disconnected");
+    /// assert!(matches!(&error, Error::Custom(custom) if custom.get_ref().is::<Disconnected>()));
+    /// ```
+    pub fn custom<E>(error: E) -> Self
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        Self::Custom(CustomError(std::sync::Arc::new(error)))
+    }
+}
+
+/// An error wrapped by [Error::custom].
+///
+/// Two custom errors are equal if they were wrapped by the same call to [Error::custom].
+#[cfg(feature = "std")]
+#[derive(thiserror::Error, Clone, Debug)]
+#[error(transparent)]
+pub struct CustomError(std::sync::Arc<dyn std::error::Error + Send + Sync>);
+
+#[cfg(feature = "std")]
+impl CustomError {
+    /// Get the wrapped error.
+    pub fn get_ref(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+        &*self.0
+    }
+}
+
+#[cfg(feature = "std")]
+impl PartialEq for CustomError {
+    fn eq(&self, other: &Self) -> bool {
+        std::sync::Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Eq for CustomError {}
+
+#[cfg(feature = "std")]
+impl core::hash::Hash for CustomError {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        std::sync::Arc::as_ptr(&self.0).cast::<()>().hash(state);
+    }
+}
+
+impl From<core::convert::Infallible> for Error {
+    fn from(infallible: core::convert::Infallible) -> Self {
+        match infallible {}
+    }
+}
+
+/// A [Memory] with a fixed amount of cells, stored in an array.
+///
+/// The address of a cell is its index in the array.
+/// Accessing an address at or above `SIZE` returns [Error::AddressOutOfRange],
+/// accessing a negative address returns [Error::NegativeAddress].
+///
+/// ```
+/// # use qelbus::{Memory, Subleq, memory::{Error, LinearMemory}};
+/// let memory = LinearMemory::<i32, 16>::from_slice(&[3, 3, -1]).unwrap();
+///
+/// let mut subleq = Subleq::new(memory);
+/// subleq.run().unwrap();
+/// assert_eq!(subleq.memory.get(&0), Ok(&3));
+/// assert_eq!(subleq.memory.get(&16), Err(Error::AddressOutOfRange(16)));
+/// assert_eq!(subleq.memory.get(&-1), Err(Error::NegativeAddress(-1)));
+/// ```
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct LinearMemory<T, const SIZE: usize>([T; SIZE]);
+
+impl<T, const SIZE: usize> LinearMemory<T, SIZE>
+where
+    T: Zero + Copy,
+{
+    /// Construct a new [LinearMemory] where every cell is 0.
+    pub fn new() -> Self {
+        Self([T::zero(); SIZE])
+    }
+
+    /// Construct a new [LinearMemory] with `data` stored at the start of it.
+    /// The remaining cells are 0.
+    ///
+    /// ```
+    /// # use qelbus::memory::{Error, LinearMemory};
+    /// assert_eq!(
+    ///     LinearMemory::<i32, 2>::from_slice(&[3, 3, -1]),
+    ///     Err(Error::ProgramTooLarge { len: 3, capacity: 2 }),
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// Returns [Error::ProgramTooLarge] if `data` is longer than `SIZE`.
+    pub fn from_slice(data: &[T]) -> Result<Self, Error> {
+        if data.len() > SIZE {
+            return Err(Error::ProgramTooLarge {
+                len: data.len(),
+                capacity: SIZE,
+            });
+        }
+
+        let mut memory = Self::new();
+        memory.0[..data.len()].copy_from_slice(data);
+        Ok(memory)
+    }
+}
+
+impl<T, const SIZE: usize> LinearMemory<T, SIZE> {
+    /// Get the cells as a slice, indexed by their addresses.
+    ///
+    /// ```
+    /// # use qelbus::memory::LinearMemory;
+    /// let memory = LinearMemory::<i32, 4>::from_slice(&[3, 3, -1]).unwrap();
+    /// assert_eq!(memory.as_slice(), [3, 3, -1, 0]);
+    /// ```
+    pub fn as_slice(&self) -> &[T] {
+        &self.0
+    }
+
+    /// Get the cells as a mutable slice, indexed by their addresses.
+    ///
+    /// ```
+    /// # use qelbus::{Memory, memory::LinearMemory};
+    /// let mut memory = LinearMemory::<i32, 4>::new();
+    /// memory.as_mut_slice()[1..].copy_from_slice(&[1, 2, 3]);
+    /// assert_eq!(memory.get(&3), Ok(&3));
+    /// ```
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.0
+    }
+}
+
+impl<T, const SIZE: usize> LinearMemory<T, SIZE>
+where
+    T: core::fmt::Display,
+{
+    /// Format the cells as a grid of `width` cells per row, each row starting with the address of its first cell.
+    ///
+    /// The addresses and cells are right-aligned, which is meant for inspecting the memory by eye.
+    ///
+    /// ```
+    /// # use qelbus::memory::LinearMemory;
+    /// let memory = LinearMemory::<i32, 10>::from_slice(&[9, 9, 3, 9, 9, -1, 100]).unwrap();
+    /// assert_eq!(
+    ///     memory.dump(4),
+    ///     "0:   9   9   3   9\n4:   9  -1 100   0\n8:   0   0\n"
+    /// );
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if `width` is 0.
+    #[cfg(feature = "std")]
+    pub fn dump(&self, width: usize) -> String {
+        use core::fmt::Write;
+
+        assert!(width > 0, "a memory dump needs at least one cell per row");
+
+        let cells: Vec<String> = self.0.iter().map(ToString::to_string).collect();
+        let cell_width = cells.iter().map(String::len).max().unwrap_or(0);
+        let address_width = SIZE.saturating_sub(1).to_string().len();
+
+        let mut dump = String::new();
+        for (row, chunk) in cells.chunks(width).enumerate() {
+            let _ = write!(dump, "{:>address_width$}:", row * width);
+            for cell in chunk {
+                let _ = write!(dump, " {cell:>cell_width$}");
+            }
+            dump.push('\n');
+        }
+        dump
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, const SIZE: usize> LinearMemory<T, SIZE>
+where
+    T: Zero + Copy + ToBytes + FromBytes<Bytes = <T as ToBytes>::Bytes>,
+{
+    /// Construct a new [LinearMemory] from cells stored as fixed-width little-endian integers.
+    ///
+    /// All bytes until the end of `reader` are read.
+    /// If they contain fewer than `SIZE` cells, the remaining cells are 0.
+    ///
+    /// ```
+    /// # use qelbus::memory::LinearMemory;
+    /// let bytes = [3, 0, 0, 0, 3, 0, 0, 0, 0xff, 0xff, 0xff, 0xff];
+    /// let memory = LinearMemory::<i32, 4>::load_le(&mut &bytes[..]).unwrap();
+    /// assert_eq!(memory, LinearMemory::from_slice(&[3, 3, -1]).unwrap());
+    ///
+    /// let mut dumped = Vec::new();
+    /// memory.dump_le(&mut dumped).unwrap();
+    /// assert_eq!(dumped[..12], bytes);
+    /// assert_eq!(dumped.len(), 16);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns the error of `reader` if reading fails.
+    /// Returns an error of kind [std::io::ErrorKind::InvalidData]
+    /// if the length isn't a multiple of the cell width or there are more than `SIZE` cells.
+    pub fn load_le<R>(reader: &mut R) -> std::io::Result<Self>
+    where
+        R: std::io::Read,
+    {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        let width = core::mem::size_of::<<T as ToBytes>::Bytes>();
+        if bytes.len() % width != 0 || bytes.len() / width > SIZE {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "{} bytes are not at most {SIZE} cells of {width} bytes",
+                    bytes.len()
+                ),
+            ));
+        }
+
+        let mut memory = Self::new();
+        for (cell, chunk) in memory.0.iter_mut().zip(bytes.chunks_exact(width)) {
+            let mut cell_bytes = T::zero().to_le_bytes();
+            cell_bytes.as_mut().copy_from_slice(chunk);
+            *cell = T::from_le_bytes(&cell_bytes);
+        }
+        Ok(memory)
+    }
+
+    /// Write all `SIZE` cells as fixed-width little-endian integers, see [Self::load_le].
+    ///
+    /// # Errors
+    /// Returns the error of `writer` if writing fails.
+    pub fn dump_le<W>(&self, writer: &mut W) -> std::io::Result<()>
+    where
+        W: std::io::Write,
+    {
+        for cell in &self.0 {
+            writer.write_all(cell.to_le_bytes().as_ref())?;
+        }
+        Ok(())
+    }
+}
+
+impl<T, const SIZE: usize> Default for LinearMemory<T, SIZE>
+where
+    T: Zero + Copy,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Serializes the cells as a sequence of `SIZE` values.
+#[cfg(feature = "serde")]
+impl<T, const SIZE: usize> serde::Serialize for LinearMemory<T, SIZE>
+where
+    T: serde::Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_seq(&self.0)
+    }
+}
+
+/// Deserializes the cells from a sequence of exactly `SIZE` values.
+///
+/// ```
+/// # use qelbus::memory::LinearMemory;
+/// # #[cfg(feature = "serde")] {
+/// let memory = LinearMemory::<i32, 3>::from_slice(&[1, 2, 3]).unwrap();
+/// let json = serde_json::to_string(&memory).unwrap();
+/// assert_eq!(serde_json::from_str::<LinearMemory<i32, 3>>(&json).unwrap(), memory);
+/// assert!(serde_json::from_str::<LinearMemory<i32, 4>>(&json).is_err());
+/// # }
+/// ```
+#[cfg(feature = "serde")]
+impl<'de, T, const SIZE: usize> serde::Deserialize<'de> for LinearMemory<T, SIZE>
+where
+    T: serde::Deserialize<'de> + Zero + Copy,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        /// Visits a sequence of exactly `SIZE` cells.
+        struct CellsVisitor<T, const SIZE: usize>(core::marker::PhantomData<T>);
+
+        impl<'de, T, const SIZE: usize> serde::de::Visitor<'de> for CellsVisitor<T, SIZE>
+        where
+            T: serde::Deserialize<'de> + Zero + Copy,
+        {
+            type Value = LinearMemory<T, SIZE>;
+
+            fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+                write!(formatter, "a sequence of {SIZE} cells")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::SeqAccess<'de>,
+            {
+                let mut memory = LinearMemory::new();
+                let mut len = 0;
+                while let Some(value) = seq.next_element()? {
+                    if len < SIZE {
+                        memory.0[len] = value;
+                    }
+                    len += 1;
+                }
+
+                if len != SIZE {
+                    return Err(serde::de::Error::invalid_length(len, &self));
+                }
+                Ok(memory)
+            }
+        }
+
+        deserializer.deserialize_seq(CellsVisitor(core::marker::PhantomData))
+    }
+}
+
+impl<T, const SIZE: usize> Memory<T> for LinearMemory<T, SIZE>
+where
+    T: Word + AsPrimitive<usize> + AsPrimitive<i64>,
+{
+    type Error = Error;
+
+    fn get(&self, index: &T) -> Result<&T, Self::Error> {
+        let index = to_index(index)?;
+        self.0.get(index).ok_or(Error::AddressOutOfRange(index))
+    }
+
+    fn set(&mut self, index: &T, value: T) -> Result<(), Self::Error> {
+        let index = to_index(index)?;
+        let cell = self
+            .0
+            .get_mut(index)
+            .ok_or(Error::AddressOutOfRange(index))?;
+        *cell = value;
+        Ok(())
+    }
+
+    /// Swaps the cells in the array, nothing is changed if either address lies outside of the memory.
+    fn swap(&mut self, a: &T, b: &T) -> Result<(), Self::Error> {
+        swap_cells(&mut self.0, a, b)
+    }
+
+    fn overflow_error(&mut self, address: &T, a: T, b: T) -> Option<Self::Error> {
+        Some(overflow(address, a, b))
+    }
+
+    fn address_overflow_error(&self, address: &T) -> Option<Self::Error> {
+        Some(address_overflow(address))
+    }
+
+    fn instruction_pointer_error(&self, address: &T) -> Option<Self::Error> {
+        Some(instruction_pointer_out_of_bounds(address))
+    }
+
+    /// Yields every cell.
+    ///
+    /// ```
+    /// # use qelbus::{Memory, memory::LinearMemory};
+    /// let memory = LinearMemory::<i32, 4>::from_slice(&[3, 3, -1]).unwrap();
+    /// let cells: Vec<_> = memory.iter_cells().collect();
+    /// assert_eq!(cells, [(0, 3), (1, 3), (2, -1), (3, 0)]);
+    /// ```
+    #[cfg(feature = "std")]
+    fn iter_cells<'a>(&'a self) -> Box<dyn Iterator<Item = (T, T)> + 'a>
+    where
+        T: 'a,
+    {
+        Box::new(enumerate_cells(&self.0))
+    }
+
+    /// Returns `Some(SIZE)`.
+    ///
+    /// ```
+    /// # use qelbus::{Memory, memory::LinearMemory};
+    /// assert_eq!(Memory::<i32>::size_hint(&LinearMemory::<i32, 16>::new()), Some(16));
+    /// ```
+    fn size_hint(&self) -> Option<usize> {
+        Some(SIZE)
+    }
+
+    /// Copies the cells out of the array at once.
+    ///
+    /// ```
+    /// # use qelbus::{Memory, memory::{Error, LinearMemory}};
+    /// let memory = LinearMemory::<i32, 4>::from_slice(&[1, 2, 3, 4]).unwrap();
+    /// assert_eq!(memory.get_range(&1, 2), Ok(vec![2, 3]));
+    /// assert_eq!(memory.get_range(&2, 3), Err(Error::AddressOutOfRange(4)));
+    /// ```
+    #[cfg(feature = "std")]
+    fn get_range(&self, start: &T, len: usize) -> Result<Vec<T>, Self::Error> {
+        let range = self.range(start, len)?;
+        Ok(self.0[range].to_vec())
+    }
+
+    /// Copies the values into the array at once.
+    /// Nothing is set if any of the cells lies outside of the memory.
+    ///
+    /// ```
+    /// # use qelbus::{Memory, memory::{Error, LinearMemory}};
+    /// let mut memory = LinearMemory::<i32, 4>::new();
+    /// memory.set_range(&1, &[2, 3]).unwrap();
+    /// assert_eq!(memory, LinearMemory::from_slice(&[0, 2, 3, 0]).unwrap());
+    /// assert_eq!(memory.set_range(&3, &[4, 5]), Err(Error::AddressOutOfRange(4)));
+    /// ```
+    fn set_range(&mut self, start: &T, values: &[T]) -> Result<(), Self::Error> {
+        let range = self.range(start, values.len())?;
+        self.0[range].copy_from_slice(values);
+        Ok(())
+    }
+
+    /// Sets every cell to 0 at once.
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        self.0.fill(T::zero());
+        Ok(())
+    }
+}
+
+impl<T, const SIZE: usize> LinearMemory<T, SIZE>
+where
+    T: Word + AsPrimitive<usize> + AsPrimitive<i64>,
+{
+    /// Set `len` consecutive cells starting at an address to 0, for example to clear the data of a program
+    /// while keeping its code. Nothing is cleared if any of the cells lies outside of the memory.
+    ///
+    /// ```
+    /// # use qelbus::{Memory, memory::{Error, LinearMemory}};
+    /// let mut memory = LinearMemory::<i32, 8>::from_slice(&[3, 3, -1, 4, 5, 6]).unwrap();
+    /// memory.clear_range(&3, 2).unwrap();
+    /// assert_eq!(&memory.as_slice()[..6], &[3, 3, -1, 0, 0, 6]);
+    /// assert_eq!(memory.clear_range(&5, 4), Err(Error::AddressOutOfRange(8)));
+    /// assert_eq!(memory.get(&5), Ok(&6));
+    /// ```
+    ///
+    /// # Errors
+    /// Returns [Error::AddressOutOfRange] or [Error::NegativeAddress] if any of the cells lies outside of the memory.
+    pub fn clear_range(&mut self, start: &T, len: usize) -> Result<(), Error> {
+        let range = self.range(start, len)?;
+        self.0[range].fill(T::zero());
+        Ok(())
+    }
+
+    /// Get the indices of `len` consecutive cells starting at an address,
+    /// or an error if any of them lies outside of the memory.
+    fn range(&self, start: &T, len: usize) -> Result<core::ops::Range<usize>, Error> {
+        let start = to_index(start)?;
+        match start.checked_add(len) {
+            Some(end) if end <= SIZE => Ok(start..end),
+            _ => Err(Error::AddressOutOfRange(start.max(SIZE))),
+        }
+    }
+}
+
+/// Convert an address into an index of a memory which only has non-negative addresses.
+fn to_index<T>(address: &T) -> Result<usize, Error>
+where
+    T: Word + AsPrimitive<usize> + AsPrimitive<i64>,
+{
+    if *address < T::zero() {
+        return Err(Error::NegativeAddress(AsPrimitive::<i64>::as_(*address)));
+    }
+    Ok(address.as_())
+}
+
+/// Swap two cells of a slice, whose addresses are their indices.
+fn swap_cells<T>(cells: &mut [T], a: &T, b: &T) -> Result<(), Error>
+where
+    T: Word + AsPrimitive<usize> + AsPrimitive<i64>,
+{
+    let a = to_index(a)?;
+    let b = to_index(b)?;
+    for index in [a, b] {
+        if index >= cells.len() {
+            return Err(Error::AddressOutOfRange(index));
+        }
+    }
+    cells.swap(a, b);
+    Ok(())
+}
+
+/// Iterate over the cells of a slice together with their addresses, which are their indices.
+#[cfg(feature = "std")]
+fn enumerate_cells<T>(cells: &[T]) -> impl Iterator<Item = (T, T)>
+where
+    T: Word,
+{
+    let addresses = core::iter::successors(Some(T::zero()), |address| {
+        Some(address.wrapping_add(&T::one()))
+    });
+    addresses.zip(cells.iter().cloned())
+}
+
+/// Construct the [Error::AddressOutOfRange] for the last argument of an instruction
+/// which lies past the end of the address space, see [Memory::address_overflow_error].
+fn address_overflow<T>(address: &T) -> Error
+where
+    T: Word + AsPrimitive<usize>,
+{
+    Error::AddressOutOfRange(AsPrimitive::<usize>::as_(*address).saturating_add(2))
+}
+
+/// Construct an [Error::InstructionPointerOutOfBounds], see [Memory::instruction_pointer_error].
+fn instruction_pointer_out_of_bounds<T>(address: &T) -> Error
+where
+    T: Word + AsPrimitive<i64>,
+{
+    Error::InstructionPointerOutOfBounds(address.as_())
+}
+
+/// Construct an [Error::Overflow], see [Memory::overflow_error].
+fn overflow<T>(address: &T, a: T, b: T) -> Error
+where
+    T: Word + AsPrimitive<usize> + AsPrimitive<i64>,
+{
+    Error::Overflow {
+        address: address.as_(),
+        a: a.as_(),
+        b: b.as_(),
+    }
+}
+
+/// A [Memory] with a fixed amount of cells, whose address space wraps around at `SIZE`.
+///
+/// Every address is taken modulo `SIZE`, so accessing a cell never fails and a negative address
+/// counts back from the end, for example `-1` is the last cell.
+/// This is the machine model of many subleq programs written for small words,
+/// like an 8-bit machine with `WrappingMemory<i8, 256>`.
+/// The instruction pointer isn't reduced, it only wraps around when the word overflows.
+///
+/// ```
+/// # use qelbus::{Memory, Subleq, memory::WrappingMemory};
+/// let mut memory = WrappingMemory::<i8, 256>::from_slice(&[-2, -1, 3, 3, 3, -1]).unwrap();
+/// memory.set(&-2, 2).unwrap();
+/// memory.set(&-1, 5).unwrap();
+///
+/// let mut subleq = Subleq::new(memory);
+/// subleq.run().unwrap();
+/// assert_eq!(subleq.memory.as_slice()[255], 3);
+/// assert_eq!(subleq.memory.get(&-1), Ok(&3));
+/// assert_eq!(subleq.memory.get(&3), Ok(&0));
+///
+/// let memory = WrappingMemory::<i32, 4>::from_slice(&[1, 2, 3, 4]).unwrap();
+/// assert_eq!(memory.get(&5), Ok(&2));
+/// assert_eq!(memory.get(&-4), Ok(&1));
+/// ```
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct WrappingMemory<T, const SIZE: usize>([T; SIZE]);
+
+impl<T, const SIZE: usize> WrappingMemory<T, SIZE>
+where
+    T: Zero + Copy,
+{
+    /// Construct a new [WrappingMemory] where every cell is 0.
+    ///
+    /// A [WrappingMemory] needs at least one cell, `SIZE` 0 fails to compile.
+    pub fn new() -> Self {
+        const { assert!(SIZE > 0, "a wrapping memory needs at least one cell") };
+        Self([T::zero(); SIZE])
+    }
+
+    /// Construct a new [WrappingMemory] with `data` stored at the start of it.
+    /// The remaining cells are 0.
+    ///
+    /// # Errors
+    /// Returns [Error::ProgramTooLarge] if `data` is longer than `SIZE`.
+    pub fn from_slice(data: &[T]) -> Result<Self, Error> {
+        if data.len() > SIZE {
+            return Err(Error::ProgramTooLarge {
+                len: data.len(),
+                capacity: SIZE,
+            });
+        }
+
+        let mut memory = Self::new();
+        memory.0[..data.len()].copy_from_slice(data);
+        Ok(memory)
+    }
+}
+
+impl<T, const SIZE: usize> WrappingMemory<T, SIZE> {
+    /// Get the cells as a slice, indexed by their addresses modulo `SIZE`.
+    pub fn as_slice(&self) -> &[T] {
+        &self.0
+    }
+
+    /// Get the cells as a mutable slice, indexed by their addresses modulo `SIZE`.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.0
+    }
+}
+
+impl<T, const SIZE: usize> WrappingMemory<T, SIZE>
+where
+    T: Word + AsPrimitive<usize> + AsPrimitive<i64>,
+{
+    /// Convert an address into the index of its cell.
+    fn index(address: &T) -> usize {
+        if *address < T::zero() {
+            let address = i128::from(AsPrimitive::<i64>::as_(*address));
+            // The remainder lies in 0..SIZE, so it fits in a usize.
+            address.rem_euclid(SIZE as i128) as usize
+        } else {
+            AsPrimitive::<usize>::as_(*address) % SIZE
+        }
+    }
+}
+
+impl<T, const SIZE: usize> Default for WrappingMemory<T, SIZE>
+where
+    T: Zero + Copy,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const SIZE: usize> Memory<T> for WrappingMemory<T, SIZE>
+where
+    T: Word + AsPrimitive<usize> + AsPrimitive<i64>,
+{
+    type Error = Error;
+
+    fn get(&self, index: &T) -> Result<&T, Self::Error> {
+        Ok(&self.0[Self::index(index)])
+    }
+
+    fn set(&mut self, index: &T, value: T) -> Result<(), Self::Error> {
+        self.0[Self::index(index)] = value;
+        Ok(())
+    }
+
+    fn swap(&mut self, a: &T, b: &T) -> Result<(), Self::Error> {
+        self.0.swap(Self::index(a), Self::index(b));
+        Ok(())
+    }
+
+    fn overflow_error(&mut self, address: &T, a: T, b: T) -> Option<Self::Error> {
+        Some(overflow(address, a, b))
+    }
+
+    fn instruction_pointer_error(&self, address: &T) -> Option<Self::Error> {
+        Some(instruction_pointer_out_of_bounds(address))
+    }
+
+    /// Returns `true`, since addresses `SIZE` apart refer to the same cell.
+    fn aliases(&self) -> bool {
+        true
+    }
+
+    /// Yields every cell once, with addresses from 0 to `SIZE - 1`.
+    #[cfg(feature = "std")]
+    fn iter_cells<'a>(&'a self) -> Box<dyn Iterator<Item = (T, T)> + 'a>
+    where
+        T: 'a,
+    {
+        Box::new(enumerate_cells(&self.0))
+    }
+
+    /// Returns `Some(SIZE)`.
+    fn size_hint(&self) -> Option<usize> {
+        Some(SIZE)
+    }
+
+    /// Sets every cell to 0 at once.
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        self.0.fill(T::zero());
+        Ok(())
+    }
+}
+
+/// A [Memory] which uses a borrowed slice as its cells.
+///
+/// Like [LinearMemory] the address of a cell is its index, but the size is not part of the type
+/// and the caller controls how the cells are allocated.
+/// Accessing an address at or above the length of the slice returns [Error::AddressOutOfRange],
+/// accessing a negative address returns [Error::NegativeAddress].
+///
+/// ```
+/// # use qelbus::{Memory, Subleq, memory::{Error, SliceMemory}};
+/// let mut cells = vec![0; 1024].into_boxed_slice();
+/// cells[..3].copy_from_slice(&[3, 3, -1]);
+///
+/// let mut subleq = Subleq::new(SliceMemory::new(&mut cells));
+/// subleq.run().unwrap();
+/// assert_eq!(subleq.memory.get(&1024), Err(Error::AddressOutOfRange(1024)));
+/// ```
+#[derive(Eq, PartialEq, Hash, Debug)]
+pub struct SliceMemory<'a, T>(&'a mut [T]);
+
+impl<'a, T> SliceMemory<'a, T> {
+    /// Construct a new [SliceMemory] which uses `cells` as its cells.
+    pub fn new(cells: &'a mut [T]) -> Self {
+        Self(cells)
+    }
+
+    /// Get back the borrowed slice.
+    pub fn into_inner(self) -> &'a mut [T] {
+        self.0
+    }
+}
+
+impl<T> Memory<T> for SliceMemory<'_, T>
+where
+    T: Word + AsPrimitive<usize> + AsPrimitive<i64>,
+{
+    type Error = Error;
+
+    fn get(&self, index: &T) -> Result<&T, Self::Error> {
+        let index = to_index(index)?;
+        self.0.get(index).ok_or(Error::AddressOutOfRange(index))
+    }
+
+    fn set(&mut self, index: &T, value: T) -> Result<(), Self::Error> {
+        let index = to_index(index)?;
+        let cell = self
+            .0
+            .get_mut(index)
+            .ok_or(Error::AddressOutOfRange(index))?;
+        *cell = value;
+        Ok(())
+    }
+
+    fn swap(&mut self, a: &T, b: &T) -> Result<(), Self::Error> {
+        swap_cells(self.0, a, b)
+    }
+
+    fn overflow_error(&mut self, address: &T, a: T, b: T) -> Option<Self::Error> {
+        Some(overflow(address, a, b))
+    }
+
+    fn address_overflow_error(&self, address: &T) -> Option<Self::Error> {
+        Some(address_overflow(address))
+    }
+
+    fn instruction_pointer_error(&self, address: &T) -> Option<Self::Error> {
+        Some(instruction_pointer_out_of_bounds(address))
+    }
+
+    #[cfg(feature = "std")]
+    fn iter_cells<'a>(&'a self) -> Box<dyn Iterator<Item = (T, T)> + 'a>
+    where
+        T: 'a,
+    {
+        Box::new(enumerate_cells(self.0))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+
+    /// Sets every cell to 0 at once.
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        self.0.fill(T::zero());
+        Ok(())
+    }
+}
+
+/// A [Memory] which only stores the cells that have been written to.
+///
+/// Reading an address which has never been written to returns 0.
+/// This makes it possible to use large address spaces, like the full `i32` range,
+/// without allocating memory for every address.
+///
+/// ```
+/// # use qelbus::{Memory, Subleq, memory::SparseMemory};
+/// let mut memory = SparseMemory::new();
+/// memory.set(&0, 1_000_000).unwrap();
+/// memory.set(&1, 1_000_000).unwrap();
+/// memory.set(&2, -1).unwrap();
+///
+/// let mut subleq = Subleq::new(memory);
+/// subleq.run().unwrap();
+/// assert_eq!(subleq.memory.get(&1_000_000), Ok(&0));
+/// assert_eq!(subleq.memory.get(&123_456_789), Ok(&0));
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct SparseMemory<T> {
+    /// The cells which have been written to.
+    cells: HashMap<T, T>,
+    /// The value of cells which haven't been written to.
+    zero: T,
+}
+
+#[cfg(feature = "std")]
+impl<T> SparseMemory<T>
+where
+    T: Zero,
+{
+    /// Construct a new [SparseMemory] where every cell is 0.
+    pub fn new() -> Self {
+        Self {
+            cells: HashMap::new(),
+            zero: T::zero(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Default for SparseMemory<T>
+where
+    T: Zero,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Memory<T> for SparseMemory<T>
+where
+    T: Word,
+{
+    type Error = core::convert::Infallible;
+
+    fn get(&self, index: &T) -> Result<&T, Self::Error> {
+        Ok(self.cells.get(index).unwrap_or(&self.zero))
+    }
+
+    fn set(&mut self, index: &T, value: T) -> Result<(), Self::Error> {
+        self.cells.insert(index.clone(), value);
+        Ok(())
+    }
+
+    /// Yields the cells which have been written to, in no particular order.
+    fn iter_cells<'a>(&'a self) -> Box<dyn Iterator<Item = (T, T)> + 'a>
+    where
+        T: 'a,
+    {
+        Box::new(
+            self.cells
+                .iter()
+                .map(|(address, value)| (address.clone(), value.clone())),
+        )
+    }
+
+    /// Forgets every cell which has been written to.
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        self.cells.clear();
+        Ok(())
+    }
+}
+
+/// A [Memory] which allocates its cells in pages of `PAGE` cells on the first write to them.
+///
+/// Reading from a page which has never been written to returns 0.
+/// Compared to [SparseMemory] this wastes less memory per cell
+/// when the used addresses are clustered together, which is typical for compiled programs.
+/// Accessing a negative address returns [Error::NegativeAddress].
+///
+/// ```
+/// # use qelbus::{Memory, Subleq, memory::PagedMemory};
+/// let mut memory = PagedMemory::<i32, 256>::new();
+/// memory.set(&0, 1_000_000).unwrap();
+/// memory.set(&1, 1_000_000).unwrap();
+/// memory.set(&2, -1).unwrap();
+///
+/// let mut subleq = Subleq::new(memory);
+/// subleq.run().unwrap();
+/// assert_eq!(subleq.memory.get(&123_456_789), Ok(&0));
+/// assert_eq!(subleq.memory.allocated_pages(), 2);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct PagedMemory<T, const PAGE: usize> {
+    /// The pages which have been written to, by page number.
+    pages: HashMap<usize, Box<[T; PAGE]>>,
+    /// The value of cells in pages which haven't been written to.
+    zero: T,
+}
+
+#[cfg(feature = "std")]
+impl<T, const PAGE: usize> PagedMemory<T, PAGE>
+where
+    T: Zero,
+{
+    /// Construct a new [PagedMemory] where every cell is 0.
+    ///
+    /// Fails to compile if `PAGE` is 0.
+    pub fn new() -> Self {
+        const { assert!(PAGE > 0, "pages must contain at least one cell") };
+        Self {
+            pages: HashMap::new(),
+            zero: T::zero(),
+        }
+    }
+
+    /// Get the amount of pages which have been allocated.
+    pub fn allocated_pages(&self) -> usize {
+        self.pages.len()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, const PAGE: usize> Default for PagedMemory<T, PAGE>
+where
+    T: Zero,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, const PAGE: usize> Memory<T> for PagedMemory<T, PAGE>
+where
+    T: Word + AsPrimitive<usize> + AsPrimitive<i64>,
+    usize: AsPrimitive<T>,
+{
+    type Error = Error;
+
+    fn get(&self, index: &T) -> Result<&T, Self::Error> {
+        let index = to_index(index)?;
+        Ok(self
+            .pages
+            .get(&(index / PAGE))
+            .map_or(&self.zero, |page| &page[index % PAGE]))
+    }
+
+    fn set(&mut self, index: &T, value: T) -> Result<(), Self::Error> {
+        let index = to_index(index)?;
+        let page = self
+            .pages
+            .entry(index / PAGE)
+            .or_insert_with(|| Box::new([T::zero(); PAGE]));
+        page[index % PAGE] = value;
+        Ok(())
+    }
+
+    fn overflow_error(&mut self, address: &T, a: T, b: T) -> Option<Self::Error> {
+        Some(overflow(address, a, b))
+    }
+
+    fn address_overflow_error(&self, address: &T) -> Option<Self::Error> {
+        Some(address_overflow(address))
+    }
+
+    fn instruction_pointer_error(&self, address: &T) -> Option<Self::Error> {
+        Some(instruction_pointer_out_of_bounds(address))
+    }
+
+    /// Yields the cells of the allocated pages, in no particular order.
+    ///
+    /// ```
+    /// # use qelbus::{Memory, memory::PagedMemory};
+    /// let mut memory = PagedMemory::<i32, 4>::new();
+    /// memory.set(&9, 7).unwrap();
+    /// let mut cells: Vec<_> = memory.iter_cells().collect();
+    /// cells.sort();
+    /// assert_eq!(cells, [(8, 0), (9, 7), (10, 0), (11, 0)]);
+    /// ```
+    fn iter_cells<'a>(&'a self) -> Box<dyn Iterator<Item = (T, T)> + 'a>
+    where
+        T: 'a,
+    {
+        Box::new(self.pages.iter().flat_map(|(page, cells)| {
+            cells
+                .iter()
+                .enumerate()
+                .map(move |(offset, value)| ((page * PAGE + offset).as_(), *value))
+        }))
+    }
+
+    /// Frees every page.
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        self.pages.clear();
+        Ok(())
+    }
+}
+
+/// The default maximum of a [VecMemory], in cells.
+#[cfg(feature = "std")]
+const VEC_MEMORY_MAX: usize = 1 << 16;
+
+/// A [Memory] backed by a [Vec] which grows when a cell past its end is written to.
+///
+/// Like [LinearMemory] the address of a cell is its index, but the size doesn't have to be known ahead of time.
+/// Reading a cell past the end of the vector returns 0, writing to it fills the cells up to it with 0.
+/// Accessing an address at or above the maximum, see [Self::with_max], returns [Error::AddressOutOfRange],
+/// accessing a negative address returns [Error::NegativeAddress].
+/// The maximum is 65536 cells unless set otherwise, so a stray write can't allocate without limit.
+///
+/// ```
+/// # use qelbus::{Memory, Subleq, memory::{Error, VecMemory}};
+/// let mut memory = VecMemory::with_max(1024);
+/// memory.set_range(&0, &[100, 100, 3, 0, 0, -1]).unwrap();
+///
+/// let mut subleq = Subleq::new(memory);
+/// subleq.run().unwrap();
+/// assert_eq!(subleq.memory.get(&100), Ok(&0));
+/// assert_eq!(subleq.memory.allocated_cells(), 101);
+/// assert_eq!(subleq.memory.get(&1024), Err(Error::AddressOutOfRange(1024)));
+///
+/// let mut memory = VecMemory::new();
+/// assert_eq!(memory.set(&65536, 1), Err(Error::AddressOutOfRange(65536)));
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct VecMemory<T> {
+    /// The cells up to the highest address which has been written to.
+    cells: Vec<T>,
+    /// The amount of addressable cells.
+    max: usize,
+    /// The value of cells past the end of `cells`.
+    zero: T,
+}
+
+#[cfg(feature = "std")]
+impl<T> VecMemory<T>
+where
+    T: Zero,
+{
+    /// Construct a new [VecMemory] where every cell is 0, with the default maximum of 65536 cells.
+    pub fn new() -> Self {
+        Self::from_vec(Vec::new())
+    }
+
+    /// Construct a new [VecMemory] where every cell is 0, whose addresses are below `max`.
+    ///
+    /// A `max` of [usize::MAX] lets the memory grow without limit.
+    pub fn with_max(max: usize) -> Self {
+        Self { max, ..Self::new() }
+    }
+
+    /// Construct a new [VecMemory] which starts with `cells`,
+    /// with the default maximum of 65536 cells or the length of `cells` if that is larger.
+    pub fn from_vec(cells: Vec<T>) -> Self {
+        Self {
+            max: VEC_MEMORY_MAX.max(cells.len()),
+            cells,
+            zero: T::zero(),
+        }
+    }
+
+    /// Get the amount of cells which have been allocated.
+    pub fn allocated_cells(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Check that `index` lies below the maximum.
+    fn check_max(&self, index: usize) -> Result<usize, Error> {
+        if index >= self.max {
+            Err(Error::AddressOutOfRange(index))
+        } else {
+            Ok(index)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Default for VecMemory<T>
+where
+    T: Zero,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Memory<T> for VecMemory<T>
+where
+    T: Word + AsPrimitive<usize> + AsPrimitive<i64>,
+{
+    type Error = Error;
+
+    fn get(&self, index: &T) -> Result<&T, Self::Error> {
+        let index = self.check_max(to_index(index)?)?;
+        Ok(self.cells.get(index).unwrap_or(&self.zero))
+    }
+
+    fn set(&mut self, index: &T, value: T) -> Result<(), Self::Error> {
+        let index = self.check_max(to_index(index)?)?;
+        if index >= self.cells.len() {
+            self.cells.resize(index + 1, T::zero());
+        }
+        self.cells[index] = value;
+        Ok(())
+    }
+
+    fn overflow_error(&mut self, address: &T, a: T, b: T) -> Option<Self::Error> {
+        Some(overflow(address, a, b))
+    }
+
+    fn address_overflow_error(&self, address: &T) -> Option<Self::Error> {
+        Some(address_overflow(address))
+    }
+
+    fn instruction_pointer_error(&self, address: &T) -> Option<Self::Error> {
+        Some(instruction_pointer_out_of_bounds(address))
+    }
+
+    /// Yields the allocated cells.
+    fn iter_cells<'a>(&'a self) -> Box<dyn Iterator<Item = (T, T)> + 'a>
+    where
+        T: 'a,
+    {
+        Box::new(enumerate_cells(&self.cells))
+    }
+
+    /// Returns the amount of allocated cells, since the cells past them are 0.
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.cells.len())
+    }
+
+    /// Truncates the vector to no cells, keeping the maximum.
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        self.cells.clear();
+        Ok(())
+    }
+}
+
+/// A [Memory] whose cells are stored in a memory-mapped file, enabled by the `mmap` feature.
+///
+/// The address of a cell is its index in the file, where every cell takes `size_of::<T>()` bytes
+/// in native byte order, which is the format of [LinearMemory::dump_le] on little-endian platforms.
+/// Writes go straight to the mapping, so the memory persists across runs
+/// and the operating system only loads the pages which are accessed.
+/// Accessing an address past the end of the file returns [Error::AddressOutOfRange],
+/// accessing a negative address returns [Error::NegativeAddress].
+///
+/// ```
+/// # use qelbus::{Memory, Subleq, memory::MmapMemory};
+/// let path = std::env::temp_dir().join(format!("qelbus-mmap-{}", std::process::id()));
+///
+/// // SAFETY: the file is only used by this example.
+/// let mut memory = unsafe { MmapMemory::<i32>::open(&path, 1 << 20) }.unwrap();
+/// memory.set_range(&0, &[6, 7, 3, 8, 8, -1, 2, 5]).unwrap();
+/// let mut subleq = Subleq::new(memory);
+/// subleq.run().unwrap();
+/// subleq.memory.flush().unwrap();
+/// drop(subleq);
+///
+/// // SAFETY: the file is only used by this example.
+/// let memory = unsafe { MmapMemory::<i32>::open(&path, 1 << 20) }.unwrap();
+/// assert_eq!(memory.get(&7), Ok(&3));
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+#[cfg(feature = "mmap")]
+#[derive(Debug)]
+pub struct MmapMemory<T> {
+    /// The mapping of the file.
+    map: memmap2::MmapMut,
+    #[doc(hidden)]
+    _marker: core::marker::PhantomData<T>,
+}
+
+/// The words which can be stored in a [MmapMemory], which are the primitive integers.
+///
+/// Every bit pattern is a valid value of these types, so the bytes of a file can be used as cells.
+/// This trait is sealed.
+#[cfg(feature = "mmap")]
+pub trait MmapWord: Word + AsPrimitive<usize> + AsPrimitive<i64> + sealed::Sealed {}
+
+/// Prevents implementing [MmapWord] outside of this crate.
+#[cfg(feature = "mmap")]
+mod sealed {
+    /// The supertrait which seals [MmapWord](super::MmapWord).
+    pub trait Sealed {}
+}
+
+/// Implement [MmapWord] for primitive integer types.
+#[cfg(feature = "mmap")]
+macro_rules! impl_mmap_word {
+    ($($t:ty),*) => {
+        $(
+            impl sealed::Sealed for $t {}
+            impl MmapWord for $t {}
+        )*
+    };
+}
+
+#[cfg(feature = "mmap")]
+impl_mmap_word!(
+    i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize
+);
+
+#[cfg(feature = "mmap")]
+impl<T> MmapMemory<T>
+where
+    T: MmapWord,
+{
+    /// Map the file at `path` as a memory of at least `cells` cells.
+    ///
+    /// The file is created if it doesn't exist and extended with cells of 0 if it is shorter.
+    /// All of its cells can be accessed, bytes past the last whole cell are ignored.
+    ///
+    /// # Errors
+    /// Returns the error of opening, extending or mapping the file.
+    ///
+    /// # Safety
+    /// The file must not be modified or truncated by other processes or mappings while the memory is alive,
+    /// see [memmap2::MmapMut::map_mut].
+    pub unsafe fn open<P>(path: P, cells: usize) -> std::io::Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        let len = cells.saturating_mul(core::mem::size_of::<T>()) as u64;
+        if file.metadata()?.len() < len {
+            file.set_len(len)?;
+        }
+
+        // SAFETY: the caller guarantees that the file isn't modified while it is mapped.
+        let map = unsafe { memmap2::MmapMut::map_mut(&file) }?;
+        Ok(Self {
+            map,
+            _marker: core::marker::PhantomData,
+        })
+    }
+
+    /// Write the changed cells to the file on disk, which otherwise happens at an unspecified time.
+    ///
+    /// # Errors
+    /// Returns the error of flushing the mapping.
+    pub fn flush(&self) -> std::io::Result<()> {
+        self.map.flush()
+    }
+
+    /// Get the cells as a slice, indexed by their addresses.
+    pub fn as_slice(&self) -> &[T] {
+        // SAFETY: every bit pattern is a valid `T`, see `MmapWord`.
+        let (prefix, cells, _) = unsafe { self.map.align_to::<T>() };
+        // Mappings are page aligned, which is enough for every primitive integer.
+        debug_assert!(prefix.is_empty());
+        cells
+    }
+
+    /// Get the cells as a mutable slice, indexed by their addresses.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        // SAFETY: every bit pattern is a valid `T`, see `MmapWord`.
+        let (prefix, cells, _) = unsafe { self.map.align_to_mut::<T>() };
+        debug_assert!(prefix.is_empty());
+        cells
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl<T> Memory<T> for MmapMemory<T>
+where
+    T: MmapWord,
+{
+    type Error = Error;
+
+    fn get(&self, index: &T) -> Result<&T, Self::Error> {
+        let index = to_index(index)?;
+        self.as_slice()
+            .get(index)
+            .ok_or(Error::AddressOutOfRange(index))
+    }
+
+    fn set(&mut self, index: &T, value: T) -> Result<(), Self::Error> {
+        let index = to_index(index)?;
+        let cell = self
+            .as_mut_slice()
+            .get_mut(index)
+            .ok_or(Error::AddressOutOfRange(index))?;
+        *cell = value;
+        Ok(())
+    }
+
+    fn swap(&mut self, a: &T, b: &T) -> Result<(), Self::Error> {
+        swap_cells(self.as_mut_slice(), a, b)
+    }
+
+    fn overflow_error(&mut self, address: &T, a: T, b: T) -> Option<Self::Error> {
+        Some(overflow(address, a, b))
+    }
+
+    fn address_overflow_error(&self, address: &T) -> Option<Self::Error> {
+        Some(address_overflow(address))
+    }
+
+    fn instruction_pointer_error(&self, address: &T) -> Option<Self::Error> {
+        Some(instruction_pointer_out_of_bounds(address))
+    }
+
+    fn iter_cells<'a>(&'a self) -> Box<dyn Iterator<Item = (T, T)> + 'a>
+    where
+        T: 'a,
+    {
+        Box::new(enumerate_cells(self.as_slice()))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.as_slice().len())
+    }
+
+    /// Sets every cell of the mapping to 0 at once.
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        self.as_mut_slice().fill(T::zero());
+        Ok(())
+    }
+}
+
+/// A [Memory] wrapper which maps an input and an output address to I/O.
+///
+/// Loading the value at the input address while executing an instruction
+/// consumes the next value from `input`, or -1 once the input is exhausted.
+/// Setting the value at the output address appends the value to `output`
+/// instead of storing it in the inner memory.
+/// All other accesses are delegated to the inner memory.
+///
+/// Since reading the output address returns the value stored in the inner memory,
+/// which is 0 unless it was explicitly set, an instruction `A OUT C` outputs the negation of A.
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// # use qelbus::{Memory, Subleq, memory::{MappedMemory, SparseMemory}};
+/// let mut memory = SparseMemory::new();
+/// // IN T ?; T OUT ?; Z Z -1
+/// let program = [-2, 9, 3, 9, -3, 6, 10, 10, -1];
+/// for (address, value) in (0..).zip(program) {
+///     memory.set(&address, value).unwrap();
+/// }
+///
+/// let input = "hi".bytes().map(i32::from);
+/// let memory = MappedMemory::new(memory, -2, input, -3, Vec::new());
+///
+/// let mut subleq = Subleq::new(memory);
+/// subleq.run().unwrap();
+/// assert_eq!(subleq.memory.output, vec![i32::from(b'h')]);
+/// # }
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct MappedMemory<T, M, I, O> {
+    /// The memory which all non-I/O accesses are delegated to.
+    pub memory: M,
+    /// The address which consumes a value from [Self::input] when loaded.
+    pub input_address: T,
+    /// The source of input values.
+    pub input: I,
+    /// The address which appends a value to [Self::output] when set.
+    pub output_address: T,
+    /// The sink of output values.
+    pub output: O,
+}
+
+impl<T, M, I, O> MappedMemory<T, M, I, O> {
+    /// Construct a new [MappedMemory] which maps `input_address` to `input`
+    /// and `output_address` to `output`.
+    pub fn new(memory: M, input_address: T, input: I, output_address: T, output: O) -> Self {
+        Self {
+            memory,
+            input_address,
+            input,
+            output_address,
+            output,
+        }
+    }
+}
+
+impl<T, M, I, O> Memory<T> for MappedMemory<T, M, I, O>
+where
+    T: Word,
+    M: Memory<T>,
+    I: Iterator<Item = T>,
+    O: Extend<T>,
+{
+    type Error = M::Error;
+
+    fn get(&self, index: &T) -> Result<&T, Self::Error> {
+        self.memory.get(index)
+    }
+
+    fn load(&mut self, index: &T) -> Result<T, Self::Error> {
+        if *index == self.input_address {
+            Ok(self
+                .input
+                .next()
+                .unwrap_or(T::zero().wrapping_sub(&T::one())))
+        } else {
+            self.memory.load(index)
+        }
+    }
+
+    fn set(&mut self, index: &T, value: T) -> Result<(), Self::Error> {
+        if *index == self.output_address {
+            self.output.extend(core::iter::once(value));
+            Ok(())
+        } else {
+            self.memory.set(index, value)
+        }
+    }
+
+    fn overflow_error(&mut self, address: &T, a: T, b: T) -> Option<Self::Error> {
+        self.memory.overflow_error(address, a, b)
+    }
+
+    fn address_overflow_error(&self, address: &T) -> Option<Self::Error> {
+        self.memory.address_overflow_error(address)
+    }
+
+    fn instruction_pointer_error(&self, address: &T) -> Option<Self::Error> {
+        self.memory.instruction_pointer_error(address)
+    }
+
+    fn aliases(&self) -> bool {
+        self.memory.aliases()
+    }
+
+    #[cfg(feature = "std")]
+    fn iter_cells<'a>(&'a self) -> Box<dyn Iterator<Item = (T, T)> + 'a>
+    where
+        T: 'a,
+    {
+        self.memory.iter_cells()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.memory.size_hint()
+    }
+
+    /// Clears the inner memory, without writing to the output.
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        self.memory.clear()
+    }
+}
+
+/// A [Memory] wrapper which implements the I/O convention from the
+/// [esolangs wiki](https://esolangs.org/wiki/Subleq), where address -1 is mapped to I/O.
+///
+/// - `-1 B C` reads a byte from the input and stores it at B. At the end of the input -1 is stored.
+/// - `A -1 C` writes the low byte of the value at A to the output,
+///   masked with the [output mask](Self::set_output_mask) and buffered according to the [OutputMode].
+///
+/// All other accesses are delegated to the inner memory.
+/// The instruction still jumps to C based on the result of the subtraction,
+/// programs conventionally use the address of the next instruction as C for I/O.
+///
+/// Because the memory has to know whether -1 is used as A or B,
+/// it relies on [Subleq](crate::Subleq) loading A, then B and then setting B for every instruction.
+/// Outside of executing instructions, address -1 reads as 0.
+///
+/// ```
+/// # use qelbus::{Subleq, memory::{IoMemory, LinearMemory}};
+/// // Read a byte into X, write X, write 'i', halt.
+/// let program = [-1, 12, 3, 12, -1, 6, 13, -1, 9, 14, 14, -1, 0, i32::from(b'i'), 0];
+/// let memory = LinearMemory::<i32, 16>::from_slice(&program).unwrap();
+///
+/// let mut subleq = Subleq::new(IoMemory::new(memory, b"H"));
+/// subleq.run().unwrap();
+/// assert_eq!(subleq.memory.output(), b"Hi");
+/// ```
+///
+/// An input byte is only consumed once it has been stored at B,
+/// so an instruction which fails doesn't lose it:
+///
+/// ```
+/// # use qelbus::{Memory, Subleq, memory::{Error, IoMemory, LinearMemory, Protected}};
+/// // Read a byte into X, which is read-only at first.
+/// let memory = LinearMemory::<i32, 16>::from_slice(&[-1, 3, 3, 0]).unwrap();
+/// let memory = IoMemory::new(Protected::new(memory, 3..4), b"x");
+///
+/// let mut subleq = Subleq::new(memory);
+/// assert_eq!(subleq.step(), Err(Error::ImmutableAddress(3)));
+/// assert_eq!(subleq.memory.input_log().as_bytes(), b"");
+///
+/// subleq.memory.memory.read_only = 0..0;
+/// subleq.step().unwrap();
+/// assert_eq!(subleq.memory.get(&3), Ok(&i32::from(b'x')));
+/// assert_eq!(subleq.memory.input_log().as_bytes(), b"x");
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct IoMemory<T, M> {
+    /// The memory which all non-I/O accesses are delegated to.
+    pub memory: M,
+    /// The bytes which haven't been read yet.
+    input: std::collections::VecDeque<u8>,
+    /// The bytes which have been written.
+    output: Vec<u8>,
+    /// The bits of written bytes which are kept, see [Self::set_output_mask].
+    output_mask: u8,
+    /// When written bytes become part of the output.
+    output_mode: OutputMode,
+    /// The bytes written since the last newline in [OutputMode::Line].
+    line: Vec<u8>,
+    /// Whether A of the executing instruction has been loaded, so the next load is B.
+    loaded_a: bool,
+    /// The input byte peeked by the executing instruction, `None` at the end of the input.
+    /// It is only consumed once it has been stored at B, so an aborted instruction doesn't lose it.
+    read_input: Option<Option<u8>>,
+    /// The value of address -1, outside of executing instructions.
+    zero: T,
+    /// The bytes which have been read from the input.
+    log: InputLog,
+}
+
+#[cfg(feature = "std")]
+impl<T, M> IoMemory<T, M>
+where
+    T: Zero,
+{
+    /// Construct a new [IoMemory] which reads from `input`.
+    pub fn new(memory: M, input: &[u8]) -> Self {
+        Self {
+            memory,
+            input: input.iter().copied().collect(),
+            output: Vec::new(),
+            output_mask: 0xff,
+            output_mode: OutputMode::EveryWrite,
+            line: Vec::new(),
+            loaded_a: false,
+            read_input: None,
+            zero: T::zero(),
+            log: InputLog::default(),
+        }
+    }
+
+    /// Construct a new [IoMemory] which reads the input recorded in `log`.
+    ///
+    /// A program which only depends on its input consumes the same input again,
+    /// which makes it possible to reproduce an interactive run exactly.
+    ///
+    /// ```
+    /// # use qelbus::{Subleq, memory::{InputLog, IoMemory, LinearMemory}};
+    /// // Read a byte into X, write X, halt.
+    /// let program = [-1, 9, 3, 9, -1, 6, 10, 10, -1, 0, 0];
+    /// let memory = LinearMemory::<i32, 16>::from_slice(&program).unwrap();
+    ///
+    /// let mut subleq = Subleq::new(IoMemory::new(memory.clone(), b"xyz"));
+    /// subleq.run().unwrap();
+    /// let bytes = subleq.memory.input_log().as_bytes().to_vec();
+    /// assert_eq!(bytes, b"x");
+    ///
+    /// let log = InputLog::from_bytes(&bytes);
+    /// let mut replay = Subleq::new(IoMemory::replay(memory, &log));
+    /// replay.run().unwrap();
+    /// assert_eq!(replay.memory.output(), b"x");
+    /// ```
+    pub fn replay(memory: M, log: &InputLog) -> Self {
+        Self::new(memory, log.as_bytes())
+    }
+
+    /// Append bytes to the input, which the program reads after the input that hasn't been read yet.
+    ///
+    /// This allows feeding input to a program while it is running, for example in a REPL.
+    ///
+    /// ```
+    /// # use qelbus::{Subleq, memory::{IoMemory, LinearMemory}};
+    /// // Echo bytes until the end of the input, one byte every 5 instructions.
+    /// let program = [18, 18, 3, -1, 18, 6, 18, 19, 12, 20, 20, -1, 18, -1, 15, 19, 19, 0];
+    /// let memory = LinearMemory::<i32, 32>::from_slice(&program).unwrap();
+    ///
+    /// let mut subleq = Subleq::new(IoMemory::new(memory, b"a"));
+    /// subleq.step_n(5).unwrap();
+    /// assert_eq!(subleq.memory.take_output(), b"a");
+    ///
+    /// subleq.memory.push_input(b"b");
+    /// subleq.step_n(5).unwrap();
+    /// assert_eq!(subleq.memory.take_output(), b"b");
+    /// assert_eq!(subleq.memory.output(), b"");
+    ///
+    /// subleq.run().unwrap();
+    /// assert!(subleq.halted());
+    /// ```
+    pub fn push_input(&mut self, bytes: &[u8]) {
+        self.input.extend(bytes);
+    }
+
+    /// Get the bytes which have been written to the output.
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    /// Remove and return the bytes which have been written to the output so far.
+    pub fn take_output(&mut self) -> Vec<u8> {
+        core::mem::take(&mut self.output)
+    }
+
+    /// Get the bytes which have been read from the input so far.
+    pub fn input_log(&self) -> &InputLog {
+        &self.log
+    }
+
+    /// Set the bits of written bytes which are kept, the default of `0xff` keeps the whole low byte.
+    ///
+    /// This is useful for programs which set high bits in the values they write, for example `0x7f` for 7-bit ASCII.
+    ///
+    /// ```
+    /// # use qelbus::{Subleq, memory::{IoMemory, LinearMemory}};
+    /// // Write 'h' with the high bit set, halt.
+    /// let program = [6, -1, 3, 7, 7, -1, 0x80 | i32::from(b'h')];
+    /// let memory = LinearMemory::<i32, 16>::from_slice(&program).unwrap();
+    ///
+    /// let mut subleq = Subleq::new(IoMemory::new(memory, b""));
+    /// subleq.memory.set_output_mask(0x7f);
+    /// subleq.run().unwrap();
+    /// assert_eq!(subleq.memory.output(), b"h");
+    /// ```
+    pub fn set_output_mask(&mut self, mask: u8) {
+        self.output_mask = mask;
+    }
+
+    /// Set when written bytes become part of the output, the default is [OutputMode::EveryWrite].
+    ///
+    /// Switching to [OutputMode::EveryWrite] flushes the buffered line, see [Self::flush_output].
+    ///
+    /// ```
+    /// # use qelbus::{Subleq, memory::{IoMemory, LinearMemory, OutputMode}};
+    /// // Echo bytes until the end of the input.
+    /// let program = [18, 18, 3, -1, 18, 6, 18, 19, 12, 20, 20, -1, 18, -1, 15, 19, 19, 0];
+    /// let memory = LinearMemory::<i32, 32>::from_slice(&program).unwrap();
+    ///
+    /// let mut subleq = Subleq::new(IoMemory::new(memory, b"ab\ncd"));
+    /// subleq.memory.set_output_mode(OutputMode::Line);
+    /// subleq.run().unwrap();
+    /// assert_eq!(subleq.memory.output(), b"ab\n");
+    ///
+    /// subleq.memory.flush_output();
+    /// assert_eq!(subleq.memory.output(), b"ab\ncd");
+    /// ```
+    pub fn set_output_mode(&mut self, mode: OutputMode) {
+        self.output_mode = mode;
+        if mode == OutputMode::EveryWrite {
+            self.flush_output();
+        }
+    }
+
+    /// Move the bytes buffered since the last newline in [OutputMode::Line] to the output.
+    pub fn flush_output(&mut self) {
+        self.output.append(&mut self.line);
+    }
+
+    /// Add a byte written by the program to the output according to the mask and the [OutputMode].
+    fn write_output(&mut self, byte: u8) {
+        let byte = byte & self.output_mask;
+        match self.output_mode {
+            OutputMode::EveryWrite => self.output.push(byte),
+            OutputMode::Line => {
+                self.line.push(byte);
+                if byte == b'\n' {
+                    self.flush_output();
+                }
+            }
+        }
+    }
+}
+
+/// When bytes written to an [IoMemory] become part of its output, see [IoMemory::set_output_mode].
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub enum OutputMode {
+    /// Every written byte is output immediately.
+    #[default]
+    EveryWrite,
+    /// Written bytes are buffered until a newline is written, which is output together with them.
+    Line,
+}
+
+/// The input bytes read by a program running on an [IoMemory], see [IoMemory::replay].
+#[cfg(feature = "std")]
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InputLog(Vec<u8>);
+
+#[cfg(feature = "std")]
+impl InputLog {
+    /// Construct a new [InputLog] from bytes returned by [Self::as_bytes].
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Self(bytes.to_vec())
+    }
+
+    /// Get the recorded bytes in the order they were read.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, M> Memory<T> for IoMemory<T, M>
+where
+    T: Word + AsPrimitive<u8>,
+    u8: AsPrimitive<T>,
+    M: Memory<T>,
+{
+    type Error = M::Error;
+
+    fn get(&self, index: &T) -> Result<&T, Self::Error> {
+        if *index == io_address() {
+            Ok(&self.zero)
+        } else {
+            self.memory.get(index)
+        }
+    }
+
+    fn load(&mut self, index: &T) -> Result<T, Self::Error> {
+        let loading_a = !self.loaded_a;
+
+        if *index != io_address() {
+            let result = self.memory.load(index);
+            // A failed load aborts the instruction, so the next load is A again.
+            self.loaded_a = loading_a && result.is_ok();
+            if result.is_err() {
+                self.read_input = None;
+            }
+            return result;
+        }
+
+        self.loaded_a = loading_a;
+        if loading_a {
+            let byte = self.input.front().copied();
+            self.read_input = Some(byte);
+            Ok(byte.map_or_else(io_address, u8::as_))
+        } else {
+            Ok(T::zero())
+        }
+    }
+
+    fn set(&mut self, index: &T, value: T) -> Result<(), Self::Error> {
+        self.loaded_a = false;
+
+        if *index == io_address() {
+            // B reads as 0, so the stored value is the negated value of A.
+            self.write_output(T::zero().wrapping_sub(&value).as_());
+            Ok(())
+        } else if let Some(byte) = self.read_input.take() {
+            self.memory
+                .set(index, byte.map_or_else(io_address, u8::as_))?;
+            if byte.is_some() {
+                self.log.0.extend(self.input.pop_front());
+            }
+            Ok(())
+        } else {
+            self.memory.set(index, value)
+        }
+    }
+
+    fn overflow_error(&mut self, address: &T, a: T, b: T) -> Option<Self::Error> {
+        // The instruction is aborted, so the next load is A again.
+        self.loaded_a = false;
+        self.read_input = None;
+        self.memory.overflow_error(address, a, b)
+    }
+
+    fn address_overflow_error(&self, address: &T) -> Option<Self::Error> {
+        self.memory.address_overflow_error(address)
+    }
+
+    fn instruction_pointer_error(&self, address: &T) -> Option<Self::Error> {
+        self.memory.instruction_pointer_error(address)
+    }
+
+    fn aliases(&self) -> bool {
+        self.memory.aliases()
+    }
+
+    fn iter_cells<'a>(&'a self) -> Box<dyn Iterator<Item = (T, T)> + 'a>
+    where
+        T: 'a,
+    {
+        self.memory.iter_cells()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.memory.size_hint()
+    }
+
+    /// Clears the inner memory, without writing to the output.
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        self.memory.clear()
+    }
+}
+
+/// The address which [IoMemory] maps to I/O.
+#[cfg(feature = "std")]
+fn io_address<T>() -> T
+where
+    T: Word,
+{
+    T::zero().wrapping_sub(&T::one())
+}
+
+/// A [Memory] wrapper which makes a range of addresses read-only.
+///
+/// Setting a value inside the range returns [Error::ImmutableAddress],
+/// all other accesses are delegated to the inner memory.
+/// This is useful to catch programs which accidentally overwrite their own code.
+///
+/// ```
+/// # use qelbus::{Memory, Subleq, memory::{Error, LinearMemory, Protected}};
+/// // Clearing the first cell overwrites the instruction itself.
+/// let memory = LinearMemory::<i32, 16>::from_slice(&[0, 0, -1]).unwrap();
+/// let mut subleq = Subleq::new(Protected::new(memory, 0..3));
+/// assert_eq!(subleq.step(), Err(Error::ImmutableAddress(0)));
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Protected<M, R> {
+    /// The memory which all accesses are delegated to.
+    pub memory: M,
+    /// The addresses which can't be written to.
+    pub read_only: R,
+}
+
+impl<M, R> Protected<M, R> {
+    /// Construct a new [Protected] memory which makes the addresses in `read_only` read-only.
+    pub fn new(memory: M, read_only: R) -> Self {
+        Self { memory, read_only }
+    }
+}
+
+impl<T, M, R> Memory<T> for Protected<M, R>
+where
+    T: Word + AsPrimitive<usize>,
+    M: Memory<T>,
+    M::Error: Into<Error>,
+    R: RangeBounds<T>,
+{
+    type Error = Error;
+
+    fn get(&self, index: &T) -> Result<&T, Self::Error> {
+        self.memory.get(index).map_err(Into::into)
+    }
+
+    fn load(&mut self, index: &T) -> Result<T, Self::Error> {
+        self.memory.load(index).map_err(Into::into)
+    }
+
+    fn set(&mut self, index: &T, value: T) -> Result<(), Self::Error> {
+        if self.read_only.contains(index) {
+            return Err(Error::ImmutableAddress(index.as_()));
+        }
+        self.memory.set(index, value).map_err(Into::into)
+    }
+
+    fn overflow_error(&mut self, address: &T, a: T, b: T) -> Option<Self::Error> {
+        self.memory.overflow_error(address, a, b).map(Into::into)
+    }
+
+    fn address_overflow_error(&self, address: &T) -> Option<Self::Error> {
+        self.memory.address_overflow_error(address).map(Into::into)
+    }
+
+    fn instruction_pointer_error(&self, address: &T) -> Option<Self::Error> {
+        self.memory
+            .instruction_pointer_error(address)
+            .map(Into::into)
+    }
+
+    fn aliases(&self) -> bool {
+        self.memory.aliases()
+    }
+
+    #[cfg(feature = "std")]
+    fn iter_cells<'a>(&'a self) -> Box<dyn Iterator<Item = (T, T)> + 'a>
+    where
+        T: 'a,
+    {
+        self.memory.iter_cells()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.memory.size_hint()
+    }
+
+    /// Clears the inner memory including the read-only cells, which are only protected from the program.
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        self.memory.clear().map_err(Into::into)
+    }
+}
+
+/// A translation from the addresses a program uses to the addresses of a [Memory], see [RemappedMemory].
+pub trait AddressMap<T> {
+    /// Translate a logical address used by a program into an address of the inner memory.
+    fn translate(&self, logical: T) -> usize;
+
+    /// Translate an address of the inner memory back into the logical address which is translated to it,
+    /// or `None` if no logical address is.
+    ///
+    /// This lets [RemappedMemory] list its cells with [Memory::iter_cells].
+    /// The provided implementation returns `None`, so no cells are listed.
+    fn untranslate(&self, physical: usize) -> Option<T> {
+        let _ = physical;
+        None
+    }
+}
+
+/// An [AddressMap] which adds a fixed offset to every address.
+///
+/// This relocates a program linked at address 0 to the offset.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct OffsetMap(pub usize);
+
+impl<T> AddressMap<T> for OffsetMap
+where
+    T: Word + AsPrimitive<usize>,
+    usize: AsPrimitive<T>,
+{
+    fn translate(&self, logical: T) -> usize {
+        logical.as_().wrapping_add(self.0)
+    }
+
+    /// Subtracts the offset, returning `None` for addresses below it or past the largest `T`.
+    fn untranslate(&self, physical: usize) -> Option<T> {
+        let logical = physical.checked_sub(self.0)?;
+        let address: T = logical.as_();
+        (address.as_() == logical).then_some(address)
+    }
+}
+
+/// A [Memory] wrapper which translates every address with an [AddressMap] before delegating to the inner memory.
+///
+/// This makes it possible to run a program inside a region of a memory
+/// at a different address than it was linked at, without rewriting it.
+///
+/// ```
+/// # use qelbus::{Memory, Subleq, memory::{LinearMemory, OffsetMap, RemappedMemory}};
+/// let mut memory = LinearMemory::<i32, 16>::new();
+/// memory.set_range(&4, &[6, 7, 3, 8, 8, -1, 2, 5]).unwrap();
+///
+/// let mut subleq = Subleq::new(RemappedMemory::new(memory, OffsetMap(4)));
+/// subleq.run().unwrap();
+/// assert_eq!(subleq.memory.get(&7), Ok(&3));
+/// assert_eq!(subleq.memory.memory.get(&11), Ok(&3));
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct RemappedMemory<M, A> {
+    /// The memory which all accesses are delegated to.
+    pub memory: M,
+    /// The translation applied to every address.
+    pub map: A,
+}
+
+impl<M, A> RemappedMemory<M, A> {
+    /// Construct a new [RemappedMemory] which translates addresses with `map`.
+    pub fn new(memory: M, map: A) -> Self {
+        Self { memory, map }
+    }
+}
+
+impl<M, A> RemappedMemory<M, A> {
+    /// Translate a logical address into an address of the inner memory.
+    fn translate<T>(&self, logical: &T) -> T
+    where
+        T: Word + Copy + 'static,
+        A: AddressMap<T>,
+        usize: AsPrimitive<T>,
+    {
+        self.map.translate(*logical).as_()
+    }
+}
+
+impl<T, M, A> Memory<T> for RemappedMemory<M, A>
+where
+    T: Word + AsPrimitive<usize>,
+    M: Memory<T>,
+    A: AddressMap<T>,
+    usize: AsPrimitive<T>,
+{
+    type Error = M::Error;
+
+    fn get(&self, index: &T) -> Result<&T, Self::Error> {
+        self.memory.get(&self.translate(index))
+    }
+
+    fn load(&mut self, index: &T) -> Result<T, Self::Error> {
+        let index = self.translate(index);
+        self.memory.load(&index)
+    }
+
+    fn set(&mut self, index: &T, value: T) -> Result<(), Self::Error> {
+        let index = self.translate(index);
+        self.memory.set(&index, value)
+    }
+
+    fn overflow_error(&mut self, address: &T, a: T, b: T) -> Option<Self::Error> {
+        self.memory.overflow_error(address, a, b)
+    }
+
+    fn address_overflow_error(&self, address: &T) -> Option<Self::Error> {
+        self.memory.address_overflow_error(address)
+    }
+
+    fn instruction_pointer_error(&self, address: &T) -> Option<Self::Error> {
+        self.memory.instruction_pointer_error(address)
+    }
+
+    /// Returns `true`, since an [AddressMap] may translate several logical addresses to the same one.
+    fn aliases(&self) -> bool {
+        true
+    }
+
+    /// Yields the cells of the inner memory at their logical addresses,
+    /// skipping the cells which [AddressMap::untranslate] finds no logical address for.
+    ///
+    /// ```
+    /// # use qelbus::{Memory, memory::{LinearMemory, OffsetMap, RemappedMemory}};
+    /// let memory = LinearMemory::<i32, 4>::from_slice(&[1, 2, 3, 4]).unwrap();
+    /// let memory = RemappedMemory::new(memory, OffsetMap(2));
+    /// let cells: Vec<_> = memory.iter_cells().collect();
+    /// assert_eq!(cells, [(0, 3), (1, 4)]);
+    /// ```
+    #[cfg(feature = "std")]
+    fn iter_cells<'a>(&'a self) -> Box<dyn Iterator<Item = (T, T)> + 'a>
+    where
+        T: 'a,
+    {
+        Box::new(self.memory.iter_cells().filter_map(|(physical, value)| {
+            let logical = self.map.untranslate(physical.as_())?;
+            Some((logical, value))
+        }))
+    }
+
+    /// Clears the inner memory, including the cells no logical address is translated to.
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        self.memory.clear()
+    }
+}
+
+/// A [Memory] wrapper which writes a line to a sink for every access, before delegating to the inner memory.
+///
+/// Reads are logged as `R addr=<address> val=<value>` and writes as `W addr=<address> val=<value>`.
+/// This includes the reads of instruction fetches, but not [Memory::iter_cells].
+/// Accesses which fail aren't logged and their errors are passed through unchanged.
+/// Errors from writing to the sink are ignored, so logging can't change how a program runs.
+///
+/// ```
+/// # use qelbus::{Subleq, memory::{LinearMemory, LoggingMemory}};
+/// let memory = LinearMemory::<i32, 4>::from_slice(&[3, 3, -1, 7]).unwrap();
+///
+/// let mut subleq = Subleq::new(LoggingMemory::new(memory, Vec::new()));
+/// subleq.run().unwrap();
+///
+/// let (_, log) = subleq.memory.into_parts();
+/// assert_eq!(
+///     String::from_utf8(log).unwrap(),
+///     "R addr=0 val=3\nR addr=1 val=3\nR addr=2 val=-1\n\
+///      R addr=3 val=7\nR addr=3 val=7\nW addr=3 val=0\n",
+/// );
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct LoggingMemory<M, W> {
+    /// The memory which all accesses are delegated to.
+    pub memory: M,
+    /// The sink the accesses are logged to, which [Memory::get] writes to through a shared reference.
+    sink: core::cell::RefCell<W>,
+}
+
+#[cfg(feature = "std")]
+impl<M, W> LoggingMemory<M, W> {
+    /// Construct a new [LoggingMemory] which logs the accesses of `memory` to `sink`.
+    pub fn new(memory: M, sink: W) -> Self {
+        Self {
+            memory,
+            sink: core::cell::RefCell::new(sink),
+        }
+    }
+
+    /// Get the sink the accesses are logged to.
+    pub fn sink_mut(&mut self) -> &mut W {
+        self.sink.get_mut()
+    }
+
+    /// Split the wrapper into the inner memory and the sink.
+    pub fn into_parts(self) -> (M, W) {
+        (self.memory, self.sink.into_inner())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<M, W> LoggingMemory<M, W>
+where
+    W: std::io::Write,
+{
+    /// Write a line for an access to the sink.
+    fn log<T>(&self, kind: char, address: &T, value: &T)
+    where
+        T: core::fmt::Display,
+    {
+        let _ = writeln!(self.sink.borrow_mut(), "{kind} addr={address} val={value}");
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, M, W> Memory<T> for LoggingMemory<M, W>
+where
+    T: Word + core::fmt::Display,
+    M: Memory<T>,
+    W: std::io::Write,
+{
+    type Error = M::Error;
+
+    fn get(&self, index: &T) -> Result<&T, Self::Error> {
+        let value = self.memory.get(index)?;
+        self.log('R', index, value);
+        Ok(value)
+    }
+
+    fn load(&mut self, index: &T) -> Result<T, Self::Error> {
+        let value = self.memory.load(index)?;
+        self.log('R', index, &value);
+        Ok(value)
+    }
+
+    fn set(&mut self, index: &T, value: T) -> Result<(), Self::Error> {
+        self.memory.set(index, value.clone())?;
+        self.log('W', index, &value);
+        Ok(())
+    }
+
+    fn overflow_error(&mut self, address: &T, a: T, b: T) -> Option<Self::Error> {
+        self.memory.overflow_error(address, a, b)
+    }
+
+    fn address_overflow_error(&self, address: &T) -> Option<Self::Error> {
+        self.memory.address_overflow_error(address)
+    }
+
+    fn instruction_pointer_error(&self, address: &T) -> Option<Self::Error> {
+        self.memory.instruction_pointer_error(address)
+    }
+
+    fn aliases(&self) -> bool {
+        self.memory.aliases()
+    }
+
+    fn iter_cells<'a>(&'a self) -> Box<dyn Iterator<Item = (T, T)> + 'a>
+    where
+        T: 'a,
+    {
+        self.memory.iter_cells()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.memory.size_hint()
+    }
+
+    /// Clears the inner memory without logging.
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        self.memory.clear()
+    }
+}
+
+/// What a cell of a [TaggedMemory] has been used as.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub enum CellKind {
+    /// The cell hasn't been accessed by an instruction.
+    #[default]
+    Unknown,
+    /// The cell has been loaded or set as an argument of an instruction.
+    Data,
+    /// The cell has been fetched as part of an instruction.
+    Code,
+}
+
+/// A [Memory] wrapper which tags every cell with what it has been used as while running a program.
+///
+/// Cells fetched with [Memory::instruction] become [CellKind::Code],
+/// cells which are loaded or set become [CellKind::Data] unless they already are code.
+/// After a run the tags are a heuristic map of the code and data of the program,
+/// for example to render them differently.
+/// Instructions are only fetched with [Memory::instruction] in [Mode::Subleq3](crate::Mode::Subleq3).
+///
+/// The tags are stored by address, so any address the inner memory accepts can be tagged,
+/// including negative ones like the I/O cell of an [IoMemory].
+///
+/// ```
+/// # use qelbus::{Subleq, memory::{CellKind, LinearMemory, TaggedMemory}};
+/// let memory = LinearMemory::<i32, 16>::from_slice(&[6, 7, 3, 8, 8, -1, 2, 5]).unwrap();
+///
+/// let mut subleq = Subleq::new(TaggedMemory::new(memory));
+/// subleq.run().unwrap();
+/// assert_eq!(subleq.memory.kind(&0), CellKind::Code);
+/// assert_eq!(subleq.memory.kind(&7), CellKind::Data);
+/// assert_eq!(subleq.memory.kind(&12), CellKind::Unknown);
+/// ```
+///
+/// ```
+/// # use qelbus::{Subleq, memory::{CellKind, IoMemory, LinearMemory, TaggedMemory}};
+/// // Write the byte at 3 to the output cell at -1.
+/// let memory = LinearMemory::<i32, 16>::from_slice(&[3, -1, 6, 72, 0, 0, 4, 4, -1]).unwrap();
+///
+/// let mut subleq = Subleq::new(TaggedMemory::new(IoMemory::new(memory, b"")));
+/// subleq.run().unwrap();
+/// assert_eq!(subleq.memory.kind(&-1), CellKind::Data);
+/// assert_eq!(subleq.memory.memory.take_output(), b"H");
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct TaggedMemory<T, M> {
+    /// The memory which all accesses are delegated to.
+    pub memory: M,
+    /// The tags of the accessed cells by address, which [Memory::instruction] updates through a shared reference.
+    kinds: core::cell::RefCell<HashMap<T, CellKind>>,
+}
+
+#[cfg(feature = "std")]
+impl<T, M> TaggedMemory<T, M> {
+    /// Construct a new [TaggedMemory] with every cell of `memory` tagged as [CellKind::Unknown].
+    pub fn new(memory: M) -> Self {
+        Self {
+            memory,
+            kinds: core::cell::RefCell::new(HashMap::new()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, M> TaggedMemory<T, M>
+where
+    T: Word,
+{
+    /// Get the tag of the cell at `address`.
+    pub fn kind(&self, address: &T) -> CellKind {
+        let kinds = self.kinds.borrow();
+        kinds.get(address).copied().unwrap_or(CellKind::Unknown)
+    }
+
+    /// Get the tags of the cells which have been accessed, by address.
+    ///
+    /// Every other cell is [CellKind::Unknown].
+    pub fn kinds(&self) -> HashMap<T, CellKind> {
+        self.kinds.borrow().clone()
+    }
+
+    /// Tag the cell at `address`, where [CellKind::Code] takes precedence over [CellKind::Data].
+    fn tag(&self, address: &T, kind: CellKind) {
+        let mut kinds = self.kinds.borrow_mut();
+        let tag = kinds.entry(address.clone()).or_default();
+        if *tag != CellKind::Code {
+            *tag = kind;
+        }
+    }
+
+    /// Tag the cells of the instruction at `address` as [CellKind::Code].
+    fn tag_instruction(&self, address: &T) {
+        let mut address = address.clone();
+        for _ in 0..3 {
+            self.tag(&address, CellKind::Code);
+            address = address.wrapping_add(&T::one());
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, M> Memory<T> for TaggedMemory<T, M>
+where
+    T: Word,
+    M: Memory<T>,
+{
+    type Error = M::Error;
+
+    fn get(&self, index: &T) -> Result<&T, Self::Error> {
+        self.memory.get(index)
+    }
+
+    fn load(&mut self, index: &T) -> Result<T, Self::Error> {
+        let value = self.memory.load(index)?;
+        self.tag(index, CellKind::Data);
+        Ok(value)
+    }
+
+    fn instruction(&self, index: &T) -> Result<crate::Instruction<T>, Self::Error> {
+        let instruction = self.memory.instruction(index)?;
+        self.tag_instruction(index);
+        Ok(instruction)
+    }
+
+    fn instruction_wrapping(&self, index: &T) -> Result<crate::Instruction<T>, Self::Error> {
+        let instruction = self.memory.instruction_wrapping(index)?;
+        self.tag_instruction(index);
+        Ok(instruction)
+    }
+
+    fn set(&mut self, index: &T, value: T) -> Result<(), Self::Error> {
+        self.memory.set(index, value)?;
+        self.tag(index, CellKind::Data);
+        Ok(())
+    }
+
+    fn overflow_error(&mut self, address: &T, a: T, b: T) -> Option<Self::Error> {
+        self.memory.overflow_error(address, a, b)
+    }
+
+    fn address_overflow_error(&self, address: &T) -> Option<Self::Error> {
+        self.memory.address_overflow_error(address)
+    }
+
+    fn instruction_pointer_error(&self, address: &T) -> Option<Self::Error> {
+        self.memory.instruction_pointer_error(address)
+    }
+
+    fn aliases(&self) -> bool {
+        self.memory.aliases()
+    }
+
+    fn iter_cells<'a>(&'a self) -> Box<dyn Iterator<Item = (T, T)> + 'a>
+    where
+        T: 'a,
+    {
+        self.memory.iter_cells()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.memory.size_hint()
+    }
+
+    /// Clears the inner memory, keeping the tags.
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        self.memory.clear()
+    }
+}
+
+/// A [Memory] wrapper which caches decoded instructions by their address.
+///
+/// [Memory::instruction] returns the cached instruction if there is one
+/// and otherwise fetches it from the inner memory and caches it.
+/// Setting a cell removes every cached instruction the cell is an argument of,
+/// so programs which modify their own code still run correctly.
+/// If the inner memory [aliases](Memory::aliases), like [WrappingMemory], the cell may also be
+/// an argument under another address, so setting a cell removes every cached instruction instead.
+/// This speeds up loops on memories where getting a cell is expensive, like [SparseMemory].
+/// Instructions are only fetched with [Memory::instruction] in [Mode::Subleq3](crate::Mode::Subleq3).
+///
+/// The inner memory is only available through a shared reference,
+/// since changing it directly would bypass the invalidation.
+///
+/// ```
+/// # use qelbus::{Instruction, Memory, RunResult, Subleq, memory::{CachedMemory, SparseMemory, WrappingMemory}};
+/// let mut memory = SparseMemory::new();
+/// // The first instruction counts down C of the third one, which then halts on the second pass.
+/// memory.set_range(&0, &[10, 8, 3, 11, 11, 6, 11, 11, 1, 0, 1, 0]).unwrap();
+///
+/// let mut subleq = Subleq::new(CachedMemory::new(memory));
+/// assert_eq!(subleq.run_capped(100).unwrap(), RunResult::Halted);
+/// assert_eq!(subleq.cycles(), 6);
+/// assert_eq!(subleq.memory.get(&8), Ok(&-1));
+/// assert_eq!(subleq.memory.cached_instructions(), 3);
+///
+/// // Address 8 is the same cell as address 0, so setting it changes the instruction at 0.
+/// let memory = WrappingMemory::<i32, 8>::from_slice(&[3, 3, -1]).unwrap();
+/// let mut memory = CachedMemory::new(memory);
+/// assert_eq!(memory.instruction(&0), Ok(Instruction { a: 3, b: 3, c: -1 }));
+/// memory.set(&8, 4).unwrap();
+/// assert_eq!(memory.instruction(&0), Ok(Instruction { a: 4, b: 3, c: -1 }));
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct CachedMemory<T, M> {
+    /// The memory which all accesses are delegated to.
+    memory: M,
+    /// The decoded instructions by address, which [Memory::instruction] fills through a shared reference.
+    cache: core::cell::RefCell<HashMap<T, crate::Instruction<T>>>,
+}
+
+#[cfg(feature = "std")]
+impl<T, M> CachedMemory<T, M> {
+    /// Construct a new [CachedMemory] with an empty cache.
+    pub fn new(memory: M) -> Self {
+        Self {
+            memory,
+            cache: core::cell::RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Get a reference to the inner memory.
+    pub fn memory(&self) -> &M {
+        &self.memory
+    }
+
+    /// Get the inner memory, discarding the cache.
+    pub fn into_inner(self) -> M {
+        self.memory
+    }
+
+    /// Get the amount of cached instructions.
+    pub fn cached_instructions(&self) -> usize {
+        self.cache.borrow().len()
+    }
+
+    /// Remove every cached instruction.
+    pub fn clear_cache(&mut self) {
+        self.cache.get_mut().clear();
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, M> Memory<T> for CachedMemory<T, M>
+where
+    T: Word,
+    M: Memory<T>,
+{
+    type Error = M::Error;
+
+    fn get(&self, index: &T) -> Result<&T, Self::Error> {
+        self.memory.get(index)
+    }
+
+    fn load(&mut self, index: &T) -> Result<T, Self::Error> {
+        self.memory.load(index)
+    }
+
+    fn instruction(&self, index: &T) -> Result<crate::Instruction<T>, Self::Error> {
+        if let Some(instruction) = self.cache.borrow().get(index) {
+            return Ok(instruction.clone());
+        }
+        let instruction = self.memory.instruction(index)?;
+        self.cache
+            .borrow_mut()
+            .insert(index.clone(), instruction.clone());
+        Ok(instruction)
+    }
+
+    fn instruction_wrapping(&self, index: &T) -> Result<crate::Instruction<T>, Self::Error> {
+        self.memory.instruction_wrapping(index)
+    }
+
+    fn set(&mut self, index: &T, value: T) -> Result<(), Self::Error> {
+        self.memory.set(index, value)?;
+        let cache = self.cache.get_mut();
+        if self.memory.aliases() {
+            cache.clear();
+        } else if !cache.is_empty() {
+            let mut address = index.clone();
+            for _ in 0..3 {
+                cache.remove(&address);
+                address = address.wrapping_sub(&T::one());
+            }
+        }
+        Ok(())
+    }
+
+    fn overflow_error(&mut self, address: &T, a: T, b: T) -> Option<Self::Error> {
+        self.memory.overflow_error(address, a, b)
+    }
+
+    fn address_overflow_error(&self, address: &T) -> Option<Self::Error> {
+        self.memory.address_overflow_error(address)
+    }
+
+    fn instruction_pointer_error(&self, address: &T) -> Option<Self::Error> {
+        self.memory.instruction_pointer_error(address)
+    }
+
+    fn aliases(&self) -> bool {
+        self.memory.aliases()
+    }
+
+    fn iter_cells<'a>(&'a self) -> Box<dyn Iterator<Item = (T, T)> + 'a>
+    where
+        T: 'a,
+    {
+        self.memory.iter_cells()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.memory.size_hint()
+    }
+
+    /// Clears the inner memory and the cache.
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        self.memory.clear()?;
+        self.cache.get_mut().clear();
+        Ok(())
+    }
+}
+
+/// A [Memory] wrapper which keeps writes in a set of overrides instead of changing the inner memory.
+///
+/// Getting a cell returns its override if there is one and otherwise the cell of the inner memory,
+/// setting a cell only changes its override.
+/// This makes it cheap to try variants of a program, like what happens if a cell had another value,
+/// without cloning the inner memory: [Self::clear_overrides] goes back to the inner memory as it was.
+///
+/// ```
+/// # use qelbus::{Memory, Subleq, memory::{LinearMemory, OverlayMemory}};
+/// let memory = LinearMemory::<i32, 16>::from_slice(&[6, 7, 3, 8, 8, -1, 2, 5]).unwrap();
+/// let mut subleq = Subleq::new(OverlayMemory::new(memory));
+///
+/// // What if cell 6 were 4 instead of 2?
+/// subleq.memory.set(&6, 4).unwrap();
+/// subleq.run().unwrap();
+/// assert_eq!(subleq.memory.get(&7), Ok(&1));
+/// assert_eq!(subleq.memory.base().get(&7), Ok(&5));
+///
+/// subleq.memory.clear_overrides();
+/// subleq.reset();
+/// subleq.run().unwrap();
+/// assert_eq!(subleq.memory.get(&7), Ok(&3));
+/// assert_eq!(subleq.memory.base().get(&7), Ok(&5));
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct OverlayMemory<T, M> {
+    /// The memory which cells without an override are read from.
+    base: M,
+    /// The values written to the memory, by address.
+    overrides: HashMap<T, T>,
+}
+
+#[cfg(feature = "std")]
+impl<T, M> OverlayMemory<T, M> {
+    /// Construct a new [OverlayMemory] without overrides.
+    ///
+    /// # Panics
+    /// Panics if `base` [aliases](Memory::aliases), since the overrides are kept by address,
+    /// so a write through one address wouldn't be seen through the others which refer to the same cell.
+    pub fn new(base: M) -> Self
+    where
+        T: Word,
+        M: Memory<T>,
+    {
+        assert!(
+            !base.aliases(),
+            "the inner memory of an OverlayMemory must not alias addresses"
+        );
+        Self {
+            base,
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// Get a reference to the inner memory, which doesn't contain the overrides.
+    pub fn base(&self) -> &M {
+        &self.base
+    }
+
+    /// Get the overrides, by address.
+    pub fn overrides(&self) -> &HashMap<T, T> {
+        &self.overrides
+    }
+
+    /// Remove every override, so the memory matches the inner memory again.
+    pub fn clear_overrides(&mut self) {
+        self.overrides.clear();
+    }
+
+    /// Get the inner memory, discarding the overrides.
+    pub fn into_inner(self) -> M {
+        self.base
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, M> OverlayMemory<T, M>
+where
+    T: Word,
+    M: Memory<T>,
+{
+    /// Write the overrides to the inner memory and return it.
+    ///
+    /// # Errors
+    /// Returns an [Memory::Error] when setting the inner [Memory] fails.
+    /// The error type is specific to the [Memory] implementation.
+    pub fn apply(mut self) -> Result<M, M::Error> {
+        for (address, value) in self.overrides {
+            self.base.set(&address, value)?;
+        }
+        Ok(self.base)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, M> Memory<T> for OverlayMemory<T, M>
+where
+    T: Word,
+    M: Memory<T>,
+{
+    type Error = M::Error;
+
+    fn get(&self, index: &T) -> Result<&T, Self::Error> {
+        match self.overrides.get(index) {
+            Some(value) => Ok(value),
+            None => self.base.get(index),
+        }
+    }
+
+    fn load(&mut self, index: &T) -> Result<T, Self::Error> {
+        match self.overrides.get(index) {
+            Some(value) => Ok(value.clone()),
+            None => self.base.load(index),
+        }
+    }
+
+    /// Overrides the cell, or returns the error of the inner memory if it can't get the cell.
+    fn set(&mut self, index: &T, value: T) -> Result<(), Self::Error> {
+        if !self.overrides.contains_key(index) {
+            self.base.get(index)?;
+        }
+        self.overrides.insert(index.clone(), value);
+        Ok(())
+    }
+
+    fn overflow_error(&mut self, address: &T, a: T, b: T) -> Option<Self::Error> {
+        self.base.overflow_error(address, a, b)
+    }
+
+    fn address_overflow_error(&self, address: &T) -> Option<Self::Error> {
+        self.base.address_overflow_error(address)
+    }
+
+    fn instruction_pointer_error(&self, address: &T) -> Option<Self::Error> {
+        self.base.instruction_pointer_error(address)
+    }
+
+    fn aliases(&self) -> bool {
+        self.base.aliases()
+    }
+
+    /// Yields the cells of the inner memory with the overrides applied,
+    /// followed by the overridden cells the inner memory doesn't yield.
+    fn iter_cells<'a>(&'a self) -> Box<dyn Iterator<Item = (T, T)> + 'a>
+    where
+        T: 'a,
+    {
+        let mut remaining: HashMap<&T, &T> = self.overrides.iter().collect();
+        let mut cells: Vec<(T, T)> = self
+            .base
+            .iter_cells()
+            .map(|(address, value)| match remaining.remove(&address) {
+                Some(value) => (address, value.clone()),
+                None => (address, value),
+            })
+            .collect();
+        cells.extend(
+            remaining
+                .into_iter()
+                .map(|(address, value)| (address.clone(), value.clone())),
+        );
+        Box::new(cells.into_iter())
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.base.size_hint()
+    }
+
+    /// Overrides every nonzero cell the inner memory yields with [Memory::iter_cells] with 0,
+    /// leaving the inner memory unchanged.
+    /// [Self::clear_overrides] goes back to the inner memory instead.
+    ///
+    /// ```
+    /// # use qelbus::{Memory, memory::{LinearMemory, OverlayMemory}};
+    /// let memory = LinearMemory::<i32, 4>::from_slice(&[3, 3, -1]).unwrap();
+    /// let mut memory = OverlayMemory::new(memory);
+    /// memory.set(&3, 5).unwrap();
+    /// memory.clear().unwrap();
+    /// assert!(memory.iter_cells().all(|(_, value)| value == 0));
+    /// assert_eq!(memory.base().get(&0), Ok(&3));
+    /// ```
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        self.overrides.clear();
+        let nonzero: Vec<T> = self
+            .base
+            .iter_cells()
+            .filter(|(_, value)| !value.is_zero())
+            .map(|(address, _)| address)
+            .collect();
+        self.overrides
+            .extend(nonzero.into_iter().map(|address| (address, T::zero())));
+        Ok(())
+    }
+}
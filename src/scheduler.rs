@@ -0,0 +1,209 @@
+//! Run several threads of execution on the memory of a single [Subleq] machine.
+//!
+//! Every thread has its own instruction pointer and halts on its own,
+//! everything else, including the memory and the counters, is shared.
+//! Threads take turns executing one instruction each, which models cooperative multitasking
+//! where the threads communicate through the shared memory.
+//!
+//! ```
+//! # use qelbus::{Memory, Subleq, memory::LinearMemory, scheduler::Scheduler};
+//! // Two threads which decrement a counter each, then halt.
+//! let program = [12, 13, 3, 14, 14, -1, 12, 15, 9, 14, 14, -1, 1, 5, 0, 7];
+//! let subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&program);
+//! let mut scheduler = Scheduler::new(subleq, [0, 6]);
+//!
+//! let mut order = Vec::new();
+//! while let Some((thread, _)) = scheduler.step().unwrap() {
+//!     order.push(thread);
+//! }
+//! assert_eq!(order, [0, 1, 0, 1]);
+//! assert_eq!(scheduler.subleq.memory.get(&13), Ok(&4));
+//! assert_eq!(scheduler.subleq.memory.get(&15), Ok(&6));
+//! assert!(scheduler.all_halted());
+//! ```
+
+use crate::{Memory, RunReport, RunResult, StepOutcome, Subleq, Word};
+
+/// The state of a single thread of a [Scheduler].
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct Thread<T> {
+    /// The address of the instruction the thread executes next.
+    curr_instruction: T,
+    /// Whether the thread has halted.
+    halted: bool,
+    /// Whether the instruction pointer of the thread went above the maximum, see [StepOutcome::IpOutOfBounds].
+    out_of_bounds: bool,
+}
+
+impl<T> Thread<T> {
+    /// Whether the thread no longer executes instructions.
+    fn stopped(&self) -> bool {
+        self.halted || self.out_of_bounds
+    }
+}
+
+/// Runs threads round-robin on a shared [Subleq] machine, see the [module documentation](self).
+///
+/// A thread is executed by swapping its instruction pointer into the machine and stepping it,
+/// so the machine's settings like the [Mode](crate::Mode) and its counters apply to all threads.
+/// The history of [Subleq::step_back] doesn't record which thread executed an instruction,
+/// so it shouldn't be used with more than one thread.
+#[derive(Debug)]
+pub struct Scheduler<T, M>
+where
+    T: Word,
+    M: Memory<T>,
+{
+    /// The machine the threads are executed on, whose instruction pointer is only meaningful during a step.
+    pub subleq: Subleq<T, M>,
+    /// The threads, indexed by their id.
+    threads: Vec<Thread<T>>,
+    /// The id of the thread which is considered first for the next step.
+    next: usize,
+}
+
+impl<T, M> Scheduler<T, M>
+where
+    T: Word,
+    M: Memory<T>,
+{
+    /// Construct a new [Scheduler] with a thread starting at each address of `entries`.
+    ///
+    /// The threads are numbered from 0 in the order of `entries`.
+    pub fn new(subleq: Subleq<T, M>, entries: impl IntoIterator<Item = T>) -> Self {
+        let mut scheduler = Self {
+            subleq,
+            threads: Vec::new(),
+            next: 0,
+        };
+        for entry in entries {
+            scheduler.spawn(entry);
+        }
+        scheduler
+    }
+
+    /// Add a thread starting at `entry` and return its id.
+    pub fn spawn(&mut self, entry: T) -> usize {
+        self.threads.push(Thread {
+            curr_instruction: entry,
+            halted: false,
+            out_of_bounds: false,
+        });
+        self.threads.len() - 1
+    }
+
+    /// Get the amount of threads.
+    pub fn threads(&self) -> usize {
+        self.threads.len()
+    }
+
+    /// Get the instruction pointer of a thread, or `None` if there is no thread with this id.
+    pub fn curr_instruction(&self, thread: usize) -> Option<&T> {
+        self.threads
+            .get(thread)
+            .map(|thread| &thread.curr_instruction)
+    }
+
+    /// Whether a thread has halted, `None` if there is no thread with this id.
+    pub fn halted(&self, thread: usize) -> Option<bool> {
+        self.threads.get(thread).map(|thread| thread.halted)
+    }
+
+    /// Whether every thread has halted.
+    pub fn all_halted(&self) -> bool {
+        self.threads.iter().all(|thread| thread.halted)
+    }
+
+    /// Execute one instruction of the next thread which hasn't halted.
+    ///
+    /// Returns the id of the thread and the outcome of its instruction,
+    /// or `None` if every thread has halted.
+    /// A thread whose outcome is [StepOutcome::IpOutOfBounds] isn't executed anymore either,
+    /// but doesn't count as halted.
+    ///
+    /// # Errors
+    /// Returns an [Memory::Error] when getting or setting [Memory] fails.
+    /// The error type is specific to the [Memory] implementation.
+    /// The thread which failed doesn't advance and is tried again on the next step.
+    pub fn step(&mut self) -> Result<Option<(usize, StepOutcome)>, M::Error> {
+        let count = self.threads.len();
+        let Some(id) = (0..count)
+            .map(|offset| (self.next + offset) % count)
+            .find(|&id| !self.threads[id].stopped())
+        else {
+            return Ok(None);
+        };
+
+        let thread = &mut self.threads[id];
+        core::mem::swap(
+            &mut self.subleq.curr_instruction,
+            &mut thread.curr_instruction,
+        );
+        self.subleq.halted = false;
+        let outcome = self.subleq.step();
+        core::mem::swap(
+            &mut self.subleq.curr_instruction,
+            &mut thread.curr_instruction,
+        );
+        let outcome = outcome?;
+
+        thread.halted = outcome == StepOutcome::Halted;
+        thread.out_of_bounds = outcome == StepOutcome::IpOutOfBounds;
+        self.next = (id + 1) % count;
+        Ok(Some((id, outcome)))
+    }
+
+    /// Execute instructions until every thread has halted.
+    ///
+    /// The reason is [RunResult::IpOutOfBounds] if the instruction pointer of a thread went above the maximum,
+    /// which stops that thread while the others keep running.
+    ///
+    /// # Errors
+    /// Returns an [Memory::Error] when getting or setting [Memory] fails.
+    /// The error type is specific to the [Memory] implementation.
+    #[must_use = "the report tells how many instructions were executed"]
+    pub fn run(&mut self) -> Result<RunReport, M::Error> {
+        let mut steps = 0;
+        loop {
+            let cycles = self.subleq.cycles;
+            if self.step()?.is_none() {
+                break;
+            }
+            if self.subleq.cycles != cycles {
+                steps += 1;
+            }
+        }
+        Ok(RunReport {
+            steps,
+            reason: self.stop_reason(),
+        })
+    }
+
+    /// Execute up to `max_steps` instructions of all threads together, until every thread has halted.
+    ///
+    /// # Errors
+    /// Returns an [Memory::Error] when getting or setting [Memory] fails.
+    /// The error type is specific to the [Memory] implementation.
+    pub fn run_capped(&mut self, max_steps: u64) -> Result<RunResult, M::Error> {
+        for _ in 0..max_steps {
+            if self.step()?.is_none() {
+                return Ok(self.stop_reason());
+            }
+        }
+
+        if self.threads.iter().all(Thread::stopped) {
+            Ok(self.stop_reason())
+        } else {
+            Ok(RunResult::LimitReached)
+        }
+    }
+
+    /// Get why the threads stopped, once none of them executes instructions anymore.
+    fn stop_reason(&self) -> RunResult {
+        if self.threads.iter().any(|thread| thread.out_of_bounds) {
+            RunResult::IpOutOfBounds
+        } else {
+            RunResult::Halted
+        }
+    }
+}
@@ -0,0 +1,240 @@
+//! An interactive debugging session on top of a [Subleq] machine.
+//!
+//! A [Session] records the execution history so execution can be stepped back,
+//! stops at breakpoints and keeps a transcript of the responses to text commands,
+//! which makes it easy to build a REPL:
+//!
+//! | command           | effect                                                     |
+//! |-------------------|------------------------------------------------------------|
+//! | `step`, `s`       | execute one instruction, see [Session::step]               |
+//! | `cont`, `c`       | run to the next breakpoint, see [Session::cont]            |
+//! | `back`            | undo the last instruction, see [Session::back]             |
+//! | `break ADDR`      | add a breakpoint, see [Session::break_at]                  |
+//! | `x ADDR LEN`      | show `LEN` cells starting at `ADDR`, see [Session::examine] |
+//!
+//! `x` shows at most 1024 cells, so a mistyped length can't exhaust the memory of the host.
+//!
+//! ```
+//! # use qelbus::{Subleq, memory::LinearMemory, session::{Session, SessionError}};
+//! let subleq = Subleq::<i32, LinearMemory<i32, 16>>::with_program(&[6, 7, 3, 8, 8, -1, 2, 5]);
+//! let mut session = Session::new(subleq);
+//!
+//! for command in ["x 6 2", "break 3", "c", "x 7 1", "back", "x 7 1", "c", "c"] {
+//!     session.command(command).unwrap();
+//! }
+//! assert_eq!(
+//!     session.take_output(),
+//!     "6: 2 5\nbreakpoint at 3\nbreakpoint at 3\n7: 3\nat 0\n7: 5\nbreakpoint at 3\nhalted\n",
+//! );
+//! assert_eq!(
+//!     session.command("x 0 100000"),
+//!     Err(SessionError::InvalidArguments("x".to_owned())),
+//! );
+//! ```
+
+use core::{fmt::Display, str::FromStr};
+use std::fmt::Write;
+
+use crate::{BreakReason, Memory, StepOutcome, Subleq, Word};
+
+/// The amount of executed instructions a [Session] can step back by default.
+const HISTORY_CAPACITY: usize = 1024;
+
+/// The amount of cells the `x` command shows at most.
+const MAX_EXAMINE_LEN: usize = 1024;
+
+/// A [Subleq] machine together with its breakpoints, execution history and a transcript of command responses.
+#[derive(Debug)]
+pub struct Session<T, M>
+where
+    T: Word,
+    M: Memory<T>,
+{
+    /// The machine which is debugged.
+    pub subleq: Subleq<T, M>,
+    /// The responses to commands which haven't been taken yet.
+    output: String,
+}
+
+/// An error returned by [Session::command].
+#[derive(thiserror::Error, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum SessionError<E> {
+    /// Getting or setting [Memory] failed.
+    #[error(transparent)]
+    Memory(E),
+    /// The command isn't one of the commands of a [Session].
+    #[error("unknown command `{0}`")]
+    UnknownCommand(String),
+    /// An argument of the command is missing, isn't a number or is out of range.
+    #[error("invalid arguments for `{0}`")]
+    InvalidArguments(String),
+}
+
+impl<T, M> Session<T, M>
+where
+    T: Word,
+    M: Memory<T>,
+{
+    /// Construct a new [Session] which debugs `subleq`.
+    ///
+    /// The history capacity of the machine is raised to at least 1024 instructions,
+    /// see [Subleq::set_history_capacity].
+    pub fn new(mut subleq: Subleq<T, M>) -> Self {
+        if subleq.history_capacity < HISTORY_CAPACITY {
+            subleq.set_history_capacity(HISTORY_CAPACITY);
+        }
+        Self {
+            subleq,
+            output: String::new(),
+        }
+    }
+
+    /// Execute a single instruction, see [Subleq::step].
+    ///
+    /// # Errors
+    /// Returns an [Memory::Error] when getting or setting [Memory] fails.
+    /// The error type is specific to the [Memory] implementation.
+    pub fn step(&mut self) -> Result<StepOutcome, M::Error> {
+        self.subleq.step()
+    }
+
+    /// Execute instructions until a breakpoint is reached or the program halts, see [Subleq::run_to_breakpoint].
+    ///
+    /// # Errors
+    /// Returns an [Memory::Error] when getting or setting [Memory] fails.
+    /// The error type is specific to the [Memory] implementation.
+    pub fn cont(&mut self) -> Result<BreakReason<T>, M::Error> {
+        self.subleq.run_to_breakpoint()
+    }
+
+    /// Undo the most recently executed instruction, see [Subleq::step_back].
+    ///
+    /// Returns whether there was an instruction to undo.
+    ///
+    /// # Errors
+    /// Returns an [Memory::Error] when setting [Memory] fails.
+    /// The error type is specific to the [Memory] implementation.
+    pub fn back(&mut self) -> Result<bool, M::Error> {
+        if self.subleq.history.is_empty() {
+            return Ok(false);
+        }
+        self.subleq.step_back()?;
+        Ok(true)
+    }
+
+    /// Stop [Self::cont] before executing the instruction at `address`.
+    pub fn break_at(&mut self, address: T) {
+        self.subleq.add_breakpoint(address);
+    }
+
+    /// Get `len` cells starting at `address`, see [Memory::get_range].
+    ///
+    /// # Errors
+    /// Returns an [Memory::Error] when getting [Memory] fails.
+    /// The error type is specific to the [Memory] implementation.
+    pub fn examine(&self, address: &T, len: usize) -> Result<Vec<T>, M::Error> {
+        self.subleq.memory.get_range(address, len)
+    }
+
+    /// Get the responses to commands which haven't been taken yet.
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+
+    /// Remove and return the responses to commands so far.
+    pub fn take_output(&mut self) -> String {
+        core::mem::take(&mut self.output)
+    }
+}
+
+impl<T, M> Session<T, M>
+where
+    T: Word + Display + FromStr,
+    M: Memory<T>,
+{
+    /// Run a text command and append its response to the output, see the [module documentation](self).
+    ///
+    /// Every response is a single line.
+    ///
+    /// # Errors
+    /// Returns a [SessionError] when the command is invalid or getting or setting [Memory] fails.
+    pub fn command(&mut self, line: &str) -> Result<(), SessionError<M::Error>> {
+        let mut tokens = line.split_whitespace();
+        let Some(name) = tokens.next() else {
+            return Ok(());
+        };
+        // Writing to a String never fails.
+        match name {
+            "step" | "s" => {
+                let _ = match self.step().map_err(SessionError::Memory)? {
+                    StepOutcome::Continued => {
+                        writeln!(self.output, "at {}", self.subleq.curr_instruction)
+                    }
+                    StepOutcome::Halted => writeln!(self.output, "halted"),
+                    StepOutcome::IpOutOfBounds => writeln!(
+                        self.output,
+                        "instruction pointer {} out of bounds",
+                        self.subleq.curr_instruction
+                    ),
+                };
+            }
+            "cont" | "c" => {
+                let _ = match self.cont().map_err(SessionError::Memory)? {
+                    BreakReason::Breakpoint(address) => {
+                        writeln!(self.output, "breakpoint at {address}")
+                    }
+                    BreakReason::Watchpoint(address) => {
+                        writeln!(self.output, "watchpoint {address} written")
+                    }
+                    BreakReason::Halted => writeln!(self.output, "halted"),
+                    BreakReason::IpOutOfBounds => writeln!(
+                        self.output,
+                        "instruction pointer {} out of bounds",
+                        self.subleq.curr_instruction
+                    ),
+                };
+            }
+            "back" => {
+                if self.back().map_err(SessionError::Memory)? {
+                    let _ = writeln!(self.output, "at {}", self.subleq.curr_instruction);
+                } else {
+                    self.output.push_str("nothing to undo\n");
+                }
+            }
+            "break" => {
+                let address: T = argument(&mut tokens, name)?;
+                let _ = writeln!(self.output, "breakpoint at {address}");
+                self.break_at(address);
+            }
+            "x" => {
+                let address: T = argument(&mut tokens, name)?;
+                let len = argument(&mut tokens, name)?;
+                if len > MAX_EXAMINE_LEN {
+                    return Err(SessionError::InvalidArguments(name.to_owned()));
+                }
+                let cells = self.examine(&address, len).map_err(SessionError::Memory)?;
+                let _ = write!(self.output, "{address}:");
+                for cell in cells {
+                    let _ = write!(self.output, " {cell}");
+                }
+                self.output.push('\n');
+            }
+            _ => return Err(SessionError::UnknownCommand(name.to_owned())),
+        }
+        Ok(())
+    }
+}
+
+/// Parse the next argument of the command `name`.
+fn argument<'a, A, E>(
+    tokens: &mut impl Iterator<Item = &'a str>,
+    name: &str,
+) -> Result<A, SessionError<E>>
+where
+    A: FromStr,
+{
+    tokens
+        .next()
+        .and_then(|token| token.parse().ok())
+        .ok_or_else(|| SessionError::InvalidArguments(name.to_owned()))
+}
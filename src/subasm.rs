@@ -0,0 +1,152 @@
+//! Compile subleq programs from a tiny macro language on top of the [assembler](crate::asm).
+//!
+//! Every line is either a line of the assembler format, which is passed through unchanged,
+//! or a pseudo-instruction, optionally preceded by label definitions.
+//! A pseudo-instruction expands to one or more subleq instructions:
+//!
+//! | pseudo-instruction | effect          | expansion                           |
+//! |--------------------|-----------------|-------------------------------------|
+//! | `ZERO x`           | `x = 0`         | `x x ?`                             |
+//! | `MOV x y`          | `y = x`         | `y y ?` `x Z ?` `Z y ?` `Z Z ?`     |
+//! | `ADD x y`          | `y = y + x`     | `x Z ?` `Z y ?` `Z Z ?`             |
+//! | `JMP label`        | jump to `label` | `Z Z label`                         |
+//! | `HALT`             | halt            | `Z Z -1`                            |
+//!
+//! The expansions use a scratch cell labelled `Z`, which [compile] appends to the end of the program.
+//! `Z` and the names of the pseudo-instructions can't be used as labels.
+//!
+//! ```text
+//! ; compute Y = X + X + X
+//!       MOV X Y
+//!       ADD X Y
+//!       ADD X Y
+//!       HALT
+//!
+//! X: 7
+//! Y: 0
+//! ```
+
+use num::{FromPrimitive, Num};
+
+use crate::asm::{AssembleError, assemble};
+
+/// An error while compiling a program.
+#[derive(thiserror::Error, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum CompileError {
+    /// The expanded program couldn't be assembled.
+    #[error(transparent)]
+    Assemble(#[from] AssembleError),
+    /// A pseudo-instruction has the wrong amount of operands.
+    #[error("`{mnemonic}` takes {expected} operands, but {found} were given")]
+    OperandCount {
+        /// The name of the pseudo-instruction.
+        mnemonic: String,
+        /// The amount of operands the pseudo-instruction takes.
+        expected: usize,
+        /// The amount of operands which were given.
+        found: usize,
+    },
+}
+
+/// Compile a program into the values of its cells, see the [module documentation](self)
+/// for the pseudo-instructions.
+///
+/// ```
+/// # use qelbus::{Memory, Subleq, memory::LinearMemory, subasm::compile};
+/// let program = compile::<i32>("
+///     MOV X Y
+///     ADD X Y
+///     ADD X Y
+///     HALT
+///
+///     X: 7
+///     Y: 0
+/// ").unwrap();
+///
+/// let mut subleq = Subleq::<i32, LinearMemory<i32, 64>>::with_program(&program);
+/// subleq.run().unwrap();
+/// assert_eq!(subleq.memory.get(&34), Ok(&21));
+/// ```
+///
+/// ```
+/// # use qelbus::subasm::{CompileError, compile};
+/// assert_eq!(
+///     compile::<i32>("JMP"),
+///     Err(CompileError::OperandCount { mnemonic: "JMP".to_owned(), expected: 1, found: 0 }),
+/// );
+/// ```
+///
+/// # Errors
+/// Returns a [CompileError] if the source isn't a valid program.
+/// Every line expands to one line, so the lines of an [AssembleError] match the source,
+/// but in pseudo-instructions the column refers to the expanded instructions.
+pub fn compile<T>(source: &str) -> Result<Vec<T>, CompileError>
+where
+    T: Num + FromPrimitive,
+{
+    let mut expanded = String::new();
+
+    for line in source.lines() {
+        let code = line.split(';').next().unwrap_or_default();
+        let mut tokens = code
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|token| !token.is_empty())
+            .peekable();
+
+        let mut labels = Vec::new();
+        while let Some(label) = tokens.next_if(|token| token.ends_with(':')) {
+            labels.push(label);
+        }
+
+        let Some(mnemonic) = tokens.next() else {
+            push_line(&mut expanded, &[], code);
+            continue;
+        };
+        let operands: Vec<&str> = tokens.collect();
+
+        match expand(mnemonic, &operands)? {
+            Some(instructions) => push_line(&mut expanded, &labels, &instructions),
+            None => push_line(&mut expanded, &[], code),
+        }
+    }
+
+    expanded.push_str("Z: 0\n");
+    Ok(assemble(&expanded)?)
+}
+
+/// Expand a pseudo-instruction into subleq instructions,
+/// or return `None` if `mnemonic` isn't a pseudo-instruction.
+fn expand(mnemonic: &str, operands: &[&str]) -> Result<Option<String>, CompileError> {
+    let expected = match mnemonic {
+        "ZERO" | "JMP" => 1,
+        "MOV" | "ADD" => 2,
+        "HALT" => 0,
+        _ => return Ok(None),
+    };
+    if operands.len() != expected {
+        return Err(CompileError::OperandCount {
+            mnemonic: mnemonic.to_owned(),
+            expected,
+            found: operands.len(),
+        });
+    }
+
+    let instructions = match (mnemonic, operands) {
+        ("ZERO", [x]) => format!("{x} {x} ?"),
+        ("MOV", [x, y]) => format!("{y} {y} ? {x} Z ? Z {y} ? Z Z ?"),
+        ("ADD", [x, y]) => format!("{x} Z ? Z {y} ? Z Z ?"),
+        ("JMP", [label]) => format!("Z Z {label}"),
+        _ => "Z Z -1".to_owned(),
+    };
+    Ok(Some(instructions))
+}
+
+/// Append a line of assembler source with label definitions in front of it.
+fn push_line(expanded: &mut String, labels: &[&str], code: &str) {
+    for label in labels {
+        expanded.push_str(label);
+        expanded.push(' ');
+    }
+    expanded.push_str(code);
+    expanded.push('\n');
+}
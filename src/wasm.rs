@@ -0,0 +1,54 @@
+//! JavaScript bindings for running subleq programs in the browser.
+//!
+//! Enabled by the `wasm` feature.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{StepOutcome, Subleq, memory::LinearMemory};
+
+/// The amount of cells in the memory of a [WasmSubleq].
+const SIZE: usize = 1 << 16;
+
+/// A subleq machine with 32-bit words and a [LinearMemory] of 65536 cells,
+/// which can be used from JavaScript.
+#[wasm_bindgen]
+pub struct WasmSubleq {
+    /// The wrapped machine.
+    subleq: Subleq<i32, LinearMemory<i32, SIZE>>,
+}
+
+#[wasm_bindgen]
+impl WasmSubleq {
+    /// Construct a new machine with a program loaded at the start of its memory.
+    ///
+    /// # Errors
+    /// Returns an error if the program doesn't fit in the memory.
+    #[wasm_bindgen(constructor)]
+    pub fn new(program: &[i32]) -> Result<WasmSubleq, JsError> {
+        Ok(Self {
+            subleq: Subleq::new(LinearMemory::from_slice(program)?),
+        })
+    }
+
+    /// Execute the current instruction and return whether the program has halted.
+    ///
+    /// # Errors
+    /// Returns an error when the instruction accesses an address outside of the memory.
+    pub fn step(&mut self) -> Result<bool, JsError> {
+        Ok(self.subleq.step()? == StepOutcome::Halted)
+    }
+
+    /// Execute up to `max` instructions and return whether the program has halted.
+    ///
+    /// # Errors
+    /// Returns an error when an instruction accesses an address outside of the memory.
+    pub fn run(&mut self, max: u32) -> Result<bool, JsError> {
+        self.subleq.step_n(max as usize)?;
+        Ok(self.subleq.halted())
+    }
+
+    /// Get a copy of all cells in the memory.
+    pub fn memory(&self) -> Vec<i32> {
+        self.subleq.memory.as_slice().to_vec()
+    }
+}
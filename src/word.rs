@@ -0,0 +1,189 @@
+//! The types of values a subleq machine operates on.
+
+use core::hash::Hash;
+
+use num::{One, Zero};
+
+/// A value stored in a [Memory](crate::Memory) cell, which is also used as an address.
+///
+/// Signed and unsigned words differ in when an instruction branches
+/// and which addresses halt the program:
+///
+/// | word     | branch if     | halt address                         |
+/// |----------|---------------|--------------------------------------|
+/// | signed   | `B - A <= 0`  | any negative address                 |
+/// | unsigned | `B <= A`      | the maximum value, which is `-1` wrapped around |
+///
+/// For unsigned words `B <= A` means the subtraction either borrows or results in 0.
+///
+/// ```
+/// # use qelbus::{Subleq, memory::LinearMemory};
+/// // Z Z -1 with -1 wrapped around to 255.
+/// let mut subleq = Subleq::<u8, LinearMemory<u8, 256>>::with_program(&[3, 3, 255]);
+/// subleq.run().unwrap();
+/// assert_eq!(subleq.curr_instruction, 255);
+/// ```
+///
+/// Words only have to be [Clone], so arbitrary-precision integers can be used as well,
+/// see [BigInt](num::BigInt) with the `bigint` feature.
+pub trait Word: Clone + Ord + Hash + Zero + One {
+    /// Subtract `a` from `self`, wrapping around on overflow,
+    /// and return the difference together with whether the instruction branches.
+    fn sub_leq(self, a: Self) -> (Self, bool);
+
+    /// Like [Self::sub_leq], but return `None` if the subtraction overflows.
+    fn checked_sub_leq(self, a: Self) -> Option<(Self, bool)>;
+
+    /// Like [Self::sub_leq], but clamp the difference to the range of the word on overflow.
+    fn saturating_sub_leq(self, a: Self) -> (Self, bool);
+
+    /// Check whether jumping to this address halts the program.
+    fn is_halt_address(&self) -> bool;
+
+    /// Add `other` to `self`, wrapping around on overflow.
+    fn wrapping_add(&self, other: &Self) -> Self;
+
+    /// Subtract `other` from `self`, wrapping around on overflow.
+    fn wrapping_sub(&self, other: &Self) -> Self;
+
+    /// Add `other` to `self`, returning `None` on overflow.
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+}
+
+/// The constant 2 of a word, built from [One] so that words don't need to convert from integers.
+pub(crate) fn two<T>() -> T
+where
+    T: Word,
+{
+    T::one().wrapping_add(&T::one())
+}
+
+/// The constant 3 of a word, which is the length of an instruction.
+pub(crate) fn three<T>() -> T
+where
+    T: Word,
+{
+    two::<T>().wrapping_add(&T::one())
+}
+
+/// Implement the arithmetic methods of [Word] with the inherent methods of an integer type.
+macro_rules! impl_word_arithmetic {
+    ($t:ty) => {
+        fn wrapping_add(&self, other: &Self) -> Self {
+            <$t>::wrapping_add(*self, *other)
+        }
+
+        fn wrapping_sub(&self, other: &Self) -> Self {
+            <$t>::wrapping_sub(*self, *other)
+        }
+
+        fn checked_add(&self, other: &Self) -> Option<Self> {
+            <$t>::checked_add(*self, *other)
+        }
+    };
+}
+
+/// Implement [Word] for signed integer types.
+macro_rules! impl_signed_word {
+    ($($t:ty),*) => {
+        $(
+            impl Word for $t {
+                fn sub_leq(self, a: Self) -> (Self, bool) {
+                    let result = self.wrapping_sub(a);
+                    (result, result <= 0)
+                }
+
+                fn checked_sub_leq(self, a: Self) -> Option<(Self, bool)> {
+                    let result = self.checked_sub(a)?;
+                    Some((result, result <= 0))
+                }
+
+                fn saturating_sub_leq(self, a: Self) -> (Self, bool) {
+                    let result = self.saturating_sub(a);
+                    (result, result <= 0)
+                }
+
+                fn is_halt_address(&self) -> bool {
+                    *self < 0
+                }
+
+                impl_word_arithmetic!($t);
+            }
+        )*
+    };
+}
+
+/// Implement [Word] for unsigned integer types.
+macro_rules! impl_unsigned_word {
+    ($($t:ty),*) => {
+        $(
+            impl Word for $t {
+                fn sub_leq(self, a: Self) -> (Self, bool) {
+                    (self.wrapping_sub(a), self <= a)
+                }
+
+                fn checked_sub_leq(self, a: Self) -> Option<(Self, bool)> {
+                    Some((self.checked_sub(a)?, self <= a))
+                }
+
+                fn saturating_sub_leq(self, a: Self) -> (Self, bool) {
+                    (self.saturating_sub(a), self <= a)
+                }
+
+                fn is_halt_address(&self) -> bool {
+                    *self == <$t>::MAX
+                }
+
+                impl_word_arithmetic!($t);
+            }
+        )*
+    };
+}
+
+impl_signed_word!(i8, i16, i32, i64, i128, isize);
+impl_unsigned_word!(u8, u16, u32, u64, u128, usize);
+
+/// Arbitrary-precision words never overflow, so every variant of the subtraction is exact.
+///
+/// ```
+/// # use num::BigInt;
+/// # use qelbus::{Memory, Subleq, memory::SparseMemory};
+/// let mut memory = SparseMemory::<BigInt>::new();
+/// memory.set_range(&BigInt::from(0), &[6, 7, 3, 6, 6, -1, -(1i128 << 100), 1].map(BigInt::from)).unwrap();
+///
+/// let mut subleq = Subleq::new(memory);
+/// subleq.run().unwrap();
+/// assert_eq!(subleq.memory.get(&BigInt::from(7)), Ok(&((BigInt::from(1) << 100) + 1)));
+/// ```
+#[cfg(feature = "bigint")]
+impl Word for num::BigInt {
+    fn sub_leq(self, a: Self) -> (Self, bool) {
+        let result = self - a;
+        let branch = result <= Self::zero();
+        (result, branch)
+    }
+
+    fn checked_sub_leq(self, a: Self) -> Option<(Self, bool)> {
+        Some(self.sub_leq(a))
+    }
+
+    fn saturating_sub_leq(self, a: Self) -> (Self, bool) {
+        self.sub_leq(a)
+    }
+
+    fn is_halt_address(&self) -> bool {
+        *self < Self::zero()
+    }
+
+    fn wrapping_add(&self, other: &Self) -> Self {
+        self + other
+    }
+
+    fn wrapping_sub(&self, other: &Self) -> Self {
+        self - other
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+}